@@ -301,13 +301,13 @@ fn use_sidewalk_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
                 }
 
                 if fwds {
-                    if osm_tags.get(osm::SIDEWALK) == Some(&"left".to_string()) {
+                    if osm::has_value(osm_tags, osm::SIDEWALK, "left") {
                         osm_tags.insert(osm::SIDEWALK.to_string(), "both".to_string());
                     } else {
                         osm_tags.insert(osm::SIDEWALK.to_string(), "right".to_string());
                     }
                 } else {
-                    if osm_tags.get(osm::SIDEWALK) == Some(&"right".to_string()) {
+                    if osm::has_value(osm_tags, osm::SIDEWALK, "right") {
                         osm_tags.insert(osm::SIDEWALK.to_string(), "both".to_string());
                     } else {
                         osm_tags.insert(osm::SIDEWALK.to_string(), "left".to_string());
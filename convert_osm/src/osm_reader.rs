@@ -71,7 +71,7 @@ pub fn extract_osm(
         osm_node_ids.insert(pt.to_hashable(), node.id);
 
         let tags = tags_to_map(&node.tags);
-        if tags.get(osm::HIGHWAY) == Some(&"traffic_signals".to_string()) {
+        if osm::has_value(&tags, osm::HIGHWAY, "traffic_signals") {
             traffic_signals.insert(pt.to_hashable());
         }
         if let Some(amenity) = tags.get("amenity") {
@@ -125,9 +125,9 @@ pub fn extract_osm(
             if !tags.contains_key(osm::PARKING_LEFT)
                 && !tags.contains_key(osm::PARKING_RIGHT)
                 && !tags.contains_key(osm::PARKING_BOTH)
-                && tags.get(osm::HIGHWAY) != Some(&"motorway".to_string())
-                && tags.get(osm::HIGHWAY) != Some(&"motorway_link".to_string())
-                && tags.get("junction") != Some(&"roundabout".to_string())
+                && !osm::has_value(&tags, osm::HIGHWAY, "motorway")
+                && !osm::has_value(&tags, osm::HIGHWAY, "motorway_link")
+                && !osm::has_value(&tags, "junction", "roundabout")
             {
                 tags.insert(osm::PARKING_BOTH.to_string(), "no_parking".to_string());
                 tags.insert(osm::INFERRED_PARKING.to_string(), "true".to_string());
@@ -137,14 +137,14 @@ pub fn extract_osm(
             // it's inferred.
             if !tags.contains_key(osm::SIDEWALK) {
                 tags.insert(osm::INFERRED_SIDEWALKS.to_string(), "true".to_string());
-                if tags.get(osm::HIGHWAY) == Some(&"motorway".to_string())
-                    || tags.get(osm::HIGHWAY) == Some(&"motorway_link".to_string())
-                    || tags.get("junction") == Some(&"roundabout".to_string())
+                if osm::has_value(&tags, osm::HIGHWAY, "motorway")
+                    || osm::has_value(&tags, osm::HIGHWAY, "motorway_link")
+                    || osm::has_value(&tags, "junction", "roundabout")
                 {
                     tags.insert(osm::SIDEWALK.to_string(), "none".to_string());
-                } else if tags.get("oneway") == Some(&"yes".to_string()) {
+                } else if osm::has_value(&tags, "oneway", osm::YES) {
                     tags.insert(osm::SIDEWALK.to_string(), "right".to_string());
-                    if tags.get(osm::HIGHWAY) == Some(&"residential".to_string()) {
+                    if osm::has_value(&tags, osm::HIGHWAY, "residential") {
                         tags.insert(osm::SIDEWALK.to_string(), "both".to_string());
                     }
                 } else {
@@ -212,15 +212,15 @@ pub fn extract_osm(
                 polygon: Polygon::new(&pts),
                 osm_tags: tags,
             });
-        } else if tags.get("natural") == Some(&"coastline".to_string()) {
+        } else if osm::has_value(&tags, "natural", "coastline") {
             coastline_groups.push((way.id, pts));
-        } else if tags.get("amenity") == Some(&"parking".to_string()) {
+        } else if osm::has_value(&tags, "amenity", "parking") {
             // TODO Verify parking = surface or handle other cases?
             map.parking_lots.push(RawParkingLot {
                 polygon: Polygon::new(&pts),
                 osm_id: way.id,
             });
-        } else if tags.get("highway") == Some(&"service".to_string()) {
+        } else if osm::has_value(&tags, "highway", "service") {
             map.parking_aisles.push(pts);
         } else {
             // The way might be part of a relation later.
@@ -238,7 +238,7 @@ pub fn extract_osm(
         let mut tags = tags_to_map(&rel.tags);
         tags.insert(osm::OSM_REL_ID.to_string(), rel.id.to_string());
         if let Some(at) = get_area_type(&tags) {
-            if tags.get("type") == Some(&"multipolygon".to_string()) {
+            if osm::has_value(&tags, "type", "multipolygon") {
                 let mut ok = true;
                 let mut pts_per_way: Vec<(i64, Vec<Pt2D>)> = Vec::new();
                 for member in &rel.members {
@@ -273,7 +273,7 @@ pub fn extract_osm(
                     }
                 }
             }
-        } else if tags.get("type") == Some(&"restriction".to_string()) {
+        } else if osm::has_value(&tags, "type", "restriction") {
             let mut from_way_id: Option<i64> = None;
             let mut via_node_id: Option<i64> = None;
             let mut via_way_id: Option<i64> = None;
@@ -423,12 +423,22 @@ fn is_road(tags: &BTreeMap<String, String>) -> bool {
         return false;
     }
     // TODO Need to figure out how to ban cutting through in the contraction hierarchy.
-    if tags.get("access") == Some(&"private".to_string()) {
+    if osm::has_value(tags, "access", "private") {
         return false;
     }
-    if tags.get("access") == Some(&"no".to_string())
-        && tags.get("bus") != Some(&"yes".to_string())
-        && tags.get("highway") != Some(&"construction".to_string())
+    // Plazas and such are polygons, not linear roads.
+    if osm::has_value(tags, "area", osm::YES) {
+        return false;
+    }
+    // Emergency-only roads are kept; their lanes are marked as such.
+    let emergency = matches!(
+        tags.get("emergency").map(|x| x.as_str()),
+        Some("yes") | Some("designated")
+    );
+    if osm::has_value(tags, "access", osm::NO)
+        && !osm::has_value(tags, "bus", osm::YES)
+        && !osm::has_value(tags, "highway", "construction")
+        && !emergency
     {
         return false;
     }
@@ -444,7 +454,6 @@ fn is_road(tags: &BTreeMap<String, String>) -> bool {
         "track",
         "bus_guideway",
         "escape",
-        "raceway",
         "bridleway",
         "steps",
         "path",
@@ -457,10 +466,9 @@ fn is_road(tags: &BTreeMap<String, String>) -> bool {
         "elevator",
         "planned",
         "razed",
-        "corridor",
         "junction",
     ] {
-        if tags.get(osm::HIGHWAY) == Some(&value.to_string()) {
+        if osm::has_value(tags, osm::HIGHWAY, value) {
             return false;
         }
     }
@@ -473,34 +481,30 @@ fn is_bldg(tags: &BTreeMap<String, String>) -> bool {
 }
 
 fn get_area_type(tags: &BTreeMap<String, String>) -> Option<AreaType> {
-    if tags.get("leisure") == Some(&"park".to_string()) {
+    if osm::has_value(tags, "leisure", "park") {
         return Some(AreaType::Park);
     }
-    if tags.get("leisure") == Some(&"golf_course".to_string()) {
+    if osm::has_value(tags, "leisure", "golf_course") {
         return Some(AreaType::Park);
     }
-    if tags.get("natural") == Some(&"wood".to_string()) {
+    if osm::has_value(tags, "natural", "wood") {
         return Some(AreaType::Park);
     }
-    if tags.get("landuse") == Some(&"cemetery".to_string()) {
+    if osm::has_value(tags, "landuse", "cemetery") {
         return Some(AreaType::Park);
     }
-    if tags.get("natural") == Some(&"water".to_string())
-        || tags.get("waterway") == Some(&"riverbank".to_string())
-    {
+    if osm::has_value(tags, "natural", "water") || osm::has_value(tags, "waterway", "riverbank") {
         return Some(AreaType::Water);
     }
-    if tags.get("place") == Some(&"island".to_string()) {
+    if osm::has_value(tags, "place", "island") {
         return Some(AreaType::Island);
     }
     // TODO These just cover up poorly inferred road geometry now. Figure out how to use these.
     if false {
-        if tags.get("traffic_calming") == Some(&"island".to_string()) {
+        if osm::has_value(tags, "traffic_calming", "island") {
             return Some(AreaType::PedestrianIsland);
         }
-        if tags.get("highway") == Some(&"pedestrian".to_string())
-            && tags.get("area") == Some(&"yes".to_string())
-        {
+        if osm::has_value(tags, "highway", "pedestrian") && osm::has_value(tags, "area", osm::YES) {
             return Some(AreaType::PedestrianIsland);
         }
     }
@@ -5,7 +5,7 @@ use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
 use abstutil::Timer;
 use ezgui::{Drawable, GeomBatch, GfxCtx, Prerender, RewriteColor};
 use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D};
-use map_model::{Lane, LaneID, LaneType, Map, Road, TurnType, PARKING_SPOT_LENGTH};
+use map_model::{Lane, LaneID, LaneMarkings, LaneType, Map, Road, TurnType, PARKING_SPOT_LENGTH};
 
 // Split into two phases like this, because AlmostDrawLane can be created in parallel, but GPU
 // upload has to be serial.
@@ -105,18 +105,21 @@ impl DrawLane {
                 LaneType::Biking => cs.bike_lane,
                 LaneType::SharedLeftTurn => cs.driving_lane,
                 LaneType::Construction => cs.parking_lane,
+                LaneType::Shoulder => cs.parking_lane,
+                LaneType::Buffer => cs.driving_lane,
+                LaneType::Median => cs.sidewalk,
             },
             polygon.clone(),
         );
         if draw_lane_markings {
-            match lane.lane_type {
-                LaneType::Sidewalk => {
+            match lane.lane_type.markings() {
+                LaneMarkings::SidewalkTiles => {
                     draw.extend(cs.sidewalk_lines, calculate_sidewalk_lines(lane));
                 }
-                LaneType::Parking => {
+                LaneMarkings::ParkingSpots => {
                     draw.extend(cs.general_road_marking, calculate_parking_lines(map, lane));
                 }
-                LaneType::Driving | LaneType::Bus => {
+                LaneMarkings::LaneLines => {
                     draw.extend(
                         cs.general_road_marking,
                         calculate_driving_lines(map, lane, road, timer),
@@ -130,8 +133,7 @@ impl DrawLane {
                         calculate_one_way_markings(lane, road),
                     );
                 }
-                LaneType::Biking => {}
-                LaneType::SharedLeftTurn => {
+                LaneMarkings::CenterTurnLines => {
                     draw.push(
                         cs.road_center_line,
                         lane.lane_center_pts
@@ -147,7 +149,7 @@ impl DrawLane {
                             .make_polygons(Distance::meters(0.25)),
                     );
                 }
-                LaneType::Construction => {}
+                LaneMarkings::Nothing => {}
             };
         }
 
@@ -387,7 +387,10 @@ impl Model {
     pub fn edit_lanes(&mut self, id: OriginalRoad, spec: String, prerender: &Prerender) {
         self.road_deleted(id);
 
-        if let Some(s) = RoadSpec::parse(spec.clone()) {
+        if let Some(mut s) = RoadSpec::parse(spec.clone()) {
+            if s.ensure_min_driving_lane() {
+                println!("{} has no driving lanes, so using {}", spec, s);
+            }
             self.map
                 .roads
                 .get_mut(&id)
@@ -495,8 +498,7 @@ impl Model {
 
     fn road_objects(&self, id: OriginalRoad) -> Vec<Object<ID>> {
         let r = &self.map.roads[&id];
-        let unset =
-            r.synthetic() && r.osm_tags.get(osm::NAME) == Some(&"Streety McStreetFace".to_string());
+        let unset = r.synthetic() && osm::has_value(&r.osm_tags, osm::NAME, "Streety McStreetFace");
         let lanes_unknown = r.osm_tags.contains_key(osm::INFERRED_SIDEWALKS);
         let spec = r.get_spec();
         let center_pts = PolyLine::new(r.center_points.clone());
@@ -574,8 +576,8 @@ impl Model {
         result
     }
 
-    // Copied from render/lane.rs. :(
     fn lt_to_color(lt: LaneType, unset: bool, lanes_unknown: bool) -> Color {
+        // Unlike in the game, turn lanes and construction stand out here
         let color = match lt {
             LaneType::Driving => Color::BLACK,
             LaneType::Bus => Color::rgb(190, 74, 76),
@@ -584,6 +586,9 @@ impl Model {
             LaneType::Biking => Color::rgb(15, 125, 75),
             LaneType::SharedLeftTurn => Color::YELLOW,
             LaneType::Construction => Color::rgb(255, 109, 0),
+            LaneType::Shoulder => Color::grey(0.2),
+            LaneType::Buffer => Color::BLACK,
+            LaneType::Median => Color::grey(0.8),
         };
         if unset {
             Color::rgba_f(0.9, color.g, color.b, 0.5)
@@ -0,0 +1,165 @@
+// How long does inferring lanes from OSM tags take for a big map, and how much does it allocate?
+// Run with `cargo bench -p map_model`.
+
+use map_model::lane_details::get_lane_details;
+use map_model::lane_specs::{get_lane_types, get_lane_types_batch};
+use map_model::osm;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+// Counts every allocation, so changes that stop building temporary Strings show up.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// About as many ways as a large city import
+const NUM_WAYS: usize = 100_000;
+const ROUNDS: usize = 5;
+
+// A mix of common tagging, none of which produces warnings, repeated up to NUM_WAYS.
+fn synthetic_map() -> Vec<BTreeMap<String, String>> {
+    let samples = vec![
+        vec![
+            "highway=residential",
+            "sidewalk=both",
+            "parking:lane:both=parallel",
+        ],
+        vec![
+            "highway=residential",
+            "sidewalk=none",
+            "parking:lane:both=no_parking",
+        ],
+        vec![
+            "highway=primary",
+            "lanes=4",
+            "sidewalk=both",
+            "cycleway:right=lane",
+            "parking:lane:right=parallel",
+            "maxspeed=30 mph",
+        ],
+        vec![
+            "highway=secondary",
+            "oneway=yes",
+            "lanes=2",
+            "sidewalk=right",
+            "turn:lanes=left|through",
+        ],
+        vec![
+            "highway=tertiary",
+            "lanes=3",
+            "lanes:forward=1",
+            "lanes:backward=1",
+            "lanes:both_ways=1",
+            "sidewalk=both",
+        ],
+        vec!["highway=motorway", "oneway=yes", "lanes=3", "sidewalk=none"],
+        vec!["highway=service", "service=alley", "sidewalk=none"],
+        vec!["highway=footway"],
+        vec!["highway=cycleway", "oneway=yes"],
+        vec!["highway=primary", "name=Main Street"],
+    ];
+    let samples: Vec<BTreeMap<String, String>> = samples
+        .into_iter()
+        .map(|kv| {
+            kv.into_iter()
+                .map(|x| {
+                    let parts: Vec<&str> = x.split('=').collect();
+                    (parts[0].to_string(), parts[1].to_string())
+                })
+                .collect()
+        })
+        .collect();
+    samples.iter().cycle().take(NUM_WAYS).cloned().collect()
+}
+
+// Runs f a few times, reporting the fastest time and how many allocations each way needed. f
+// returns a count of what it found, like the number of lanes, just so the work can't be optimized
+// away.
+fn measure<F: FnMut() -> usize>(name: &str, mut f: F) {
+    let mut times = Vec::new();
+    let mut allocations = 0;
+    let mut count = 0;
+    for _ in 0..ROUNDS {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        count = f();
+        times.push(start.elapsed());
+        allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    }
+    let best = times.into_iter().min().unwrap();
+    println!(
+        "{}: {:?} for {} ways (found {}), {:.0}ns and {:.1} allocations per way",
+        name,
+        best,
+        NUM_WAYS,
+        count,
+        best.as_secs_f64() * 1e9 / (NUM_WAYS as f64),
+        (allocations as f64) / (NUM_WAYS as f64)
+    );
+}
+
+fn main() {
+    let ways = synthetic_map();
+
+    measure("get_lane_types, one way at a time", || {
+        ways.iter()
+            .map(|tags| {
+                let (fwd, back) = get_lane_types(tags);
+                fwd.len() + back.len()
+            })
+            .sum()
+    });
+    measure("get_lane_types_batch", || {
+        get_lane_types_batch(&ways)
+            .unwrap()
+            .into_iter()
+            .map(|(fwd, back)| fwd.len() + back.len())
+            .sum()
+    });
+    measure("get_lane_details, one way at a time", || {
+        ways.iter()
+            .map(|tags| {
+                let (fwd, back) = get_lane_details(tags);
+                fwd.len() + back.len()
+            })
+            .sum()
+    });
+
+    // The kind of comparison lane inference does constantly, with and without building a String
+    // for the value
+    let keys = [osm::HIGHWAY, "oneway", osm::SIDEWALK, "cycleway", "lanes"];
+    measure("comparing tag values against a new String", || {
+        ways.iter()
+            .map(|tags| {
+                keys.iter()
+                    .filter(|key| tags.get(**key) == Some(&osm::YES.to_string()))
+                    .count()
+            })
+            .sum()
+    });
+    measure("comparing tag values with osm::has_value", || {
+        ways.iter()
+            .map(|tags| {
+                keys.iter()
+                    .filter(|key| osm::has_value(tags, key, osm::YES))
+                    .count()
+            })
+            .sum()
+    });
+}
@@ -1,3 +1,4 @@
+use crate::lane_details::LaneDetails;
 use crate::pathfind;
 use crate::{
     osm, BuildingID, BusStopID, DirectedRoadID, IntersectionID, Map, PathConstraints, Road, RoadID,
@@ -14,6 +15,37 @@ pub const PARKING_SPOT_LENGTH: Distance = Distance::const_meters(8.0);
 // audited cases in Seattle. This is 0.8 of above
 pub const PARKING_LOT_SPOT_LENGTH: Distance = Distance::const_meters(6.4);
 
+// Some simulations want cars to take one of a fixed number of spots; others just need to know
+// how much curb is usable and pack vehicles of different lengths into it. number_parking_spots
+// is the discrete mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParkingMode {
+    Discrete,
+    Continuous,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParkingCapacity {
+    Spots(usize),
+    Length(Distance),
+}
+
+fn parking_capacity_for_length(length: Distance, mode: ParkingMode) -> ParkingCapacity {
+    // No spots next to intersections
+    let usable = length - PARKING_SPOT_LENGTH * 2.0;
+    match mode {
+        ParkingMode::Discrete => {
+            let spots = (usable / PARKING_SPOT_LENGTH).floor();
+            if spots >= 1.0 {
+                ParkingCapacity::Spots(spots as usize)
+            } else {
+                ParkingCapacity::Spots(0)
+            }
+        }
+        ParkingMode::Continuous => ParkingCapacity::Length(usable.max(Distance::ZERO)),
+    }
+}
+
 // TODO reconsider pub usize. maybe outside world shouldnt know.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct LaneID(pub usize);
@@ -33,9 +65,29 @@ pub enum LaneType {
     Bus,
     SharedLeftTurn,
     Construction,
+    Shoulder,
+    // Painted hatching, usually between a bike lane and traffic
+    Buffer,
+    // Something raised or planted between the two directions, like a curb or a strip of grass
+    Median,
 }
 
 impl LaneType {
+    pub fn all() -> Vec<LaneType> {
+        vec![
+            LaneType::Driving,
+            LaneType::Parking,
+            LaneType::Sidewalk,
+            LaneType::Biking,
+            LaneType::Bus,
+            LaneType::SharedLeftTurn,
+            LaneType::Construction,
+            LaneType::Shoulder,
+            LaneType::Buffer,
+            LaneType::Median,
+        ]
+    }
+
     pub fn is_for_moving_vehicles(self) -> bool {
         match self {
             LaneType::Driving => true,
@@ -45,6 +97,43 @@ impl LaneType {
             LaneType::Sidewalk => false,
             LaneType::SharedLeftTurn => false,
             LaneType::Construction => false,
+            LaneType::Shoulder => false,
+            LaneType::Buffer => false,
+            LaneType::Median => false,
+        }
+    }
+
+    // Lanes that motor vehicles travel along. Bikes are moving vehicles too, but they're much
+    // narrower, so geometry and collision checks treat them separately.
+    pub fn is_vehicle_lane(self) -> bool {
+        match self {
+            LaneType::Driving => true,
+            LaneType::Bus => true,
+            LaneType::Biking => false,
+            LaneType::Parking => false,
+            LaneType::Sidewalk => false,
+            LaneType::SharedLeftTurn => false,
+            LaneType::Construction => false,
+            LaneType::Shoulder => false,
+            LaneType::Buffer => false,
+            LaneType::Median => false,
+        }
+    }
+
+    // Lanes that only exist to draw something, like a painted buffer or a median, and that simple
+    // tag-derived specs wouldn't include.
+    pub fn is_cosmetic(self) -> bool {
+        match self {
+            LaneType::Driving => false,
+            LaneType::Biking => false,
+            LaneType::Bus => false,
+            LaneType::Parking => false,
+            LaneType::Sidewalk => false,
+            LaneType::SharedLeftTurn => false,
+            LaneType::Construction => false,
+            LaneType::Shoulder => false,
+            LaneType::Buffer => true,
+            LaneType::Median => true,
         }
     }
 
@@ -57,6 +146,9 @@ impl LaneType {
             LaneType::Sidewalk => true,
             LaneType::SharedLeftTurn => false,
             LaneType::Construction => false,
+            LaneType::Shoulder => false,
+            LaneType::Buffer => false,
+            LaneType::Median => false,
         }
     }
 
@@ -69,6 +161,9 @@ impl LaneType {
             LaneType::Sidewalk => "a sidewalk",
             LaneType::SharedLeftTurn => "a shared left-turn lane",
             LaneType::Construction => "a lane that's closed for construction",
+            LaneType::Shoulder => "a shoulder along the edge of the road",
+            LaneType::Buffer => "a painted buffer between lanes",
+            LaneType::Median => "a median between the two directions",
         }
     }
 
@@ -81,8 +176,38 @@ impl LaneType {
             LaneType::Sidewalk => "sidewalk",
             LaneType::SharedLeftTurn => "left-turn lane",
             LaneType::Construction => "construction",
+            LaneType::Shoulder => "shoulder",
+            LaneType::Buffer => "buffer",
+            LaneType::Median => "median",
         }
     }
+
+    // What's painted on top of the lane. Colors are up to whoever's drawing; the game uses its
+    // ColorScheme.
+    pub fn markings(self) -> LaneMarkings {
+        match self {
+            LaneType::Driving | LaneType::Bus => LaneMarkings::LaneLines,
+            LaneType::Parking => LaneMarkings::ParkingSpots,
+            LaneType::Sidewalk => LaneMarkings::SidewalkTiles,
+            LaneType::SharedLeftTurn => LaneMarkings::CenterTurnLines,
+            LaneType::Biking
+            | LaneType::Construction
+            | LaneType::Shoulder
+            | LaneType::Buffer
+            | LaneType::Median => LaneMarkings::Nothing,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LaneMarkings {
+    // Lines between lanes, turn arrows, and one-way arrows
+    LaneLines,
+    ParkingSpots,
+    SidewalkTiles,
+    // Two solid lines on either edge
+    CenterTurnLines,
+    Nothing,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -92,6 +217,9 @@ pub struct Lane {
     pub lane_type: LaneType,
     pub lane_center_pts: PolyLine,
     pub width: Distance,
+    // As imported from OSM. Map edits don't update these, so if details.lt no longer matches
+    // lane_type, the lane was edited and they don't describe it anymore.
+    pub details: LaneDetails,
 
     pub src_i: IntersectionID,
     pub dst_i: IntersectionID,
@@ -167,16 +295,17 @@ impl Lane {
     // TODO different types for each lane type might be reasonable
 
     pub fn number_parking_spots(&self) -> usize {
-        assert_eq!(self.lane_type, LaneType::Parking);
-        // No spots next to intersections
-        let spots = (self.length() / PARKING_SPOT_LENGTH).floor() - 2.0;
-        if spots >= 1.0 {
-            spots as usize
-        } else {
-            0
+        match self.parking_capacity(ParkingMode::Discrete) {
+            ParkingCapacity::Spots(n) => n,
+            ParkingCapacity::Length(_) => unreachable!(),
         }
     }
 
+    pub fn parking_capacity(&self, mode: ParkingMode) -> ParkingCapacity {
+        assert_eq!(self.lane_type, LaneType::Parking);
+        parking_capacity_for_length(self.length(), mode)
+    }
+
     pub fn is_driving(&self) -> bool {
         self.lane_type == LaneType::Driving
     }
@@ -271,3 +400,73 @@ impl Lane {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parking_capacity_modes() {
+        use super::{parking_capacity_for_length, ParkingCapacity, ParkingMode};
+        use geom::Distance;
+
+        // 5 spots fit, minus one at each end
+        let len = Distance::meters(45.0);
+        assert_eq!(
+            ParkingCapacity::Spots(3),
+            parking_capacity_for_length(len, ParkingMode::Discrete)
+        );
+        assert_eq!(
+            ParkingCapacity::Length(Distance::meters(29.0)),
+            parking_capacity_for_length(len, ParkingMode::Continuous)
+        );
+
+        let short = Distance::meters(10.0);
+        assert_eq!(
+            ParkingCapacity::Spots(0),
+            parking_capacity_for_length(short, ParkingMode::Discrete)
+        );
+        assert_eq!(
+            ParkingCapacity::Length(Distance::ZERO),
+            parking_capacity_for_length(short, ParkingMode::Continuous)
+        );
+    }
+
+    #[test]
+    fn test_vehicle_lanes() {
+        use super::LaneType;
+
+        let vehicle: Vec<LaneType> = LaneType::all()
+            .into_iter()
+            .filter(|lt| lt.is_vehicle_lane())
+            .collect();
+        assert_eq!(vec![LaneType::Driving, LaneType::Bus], vehicle);
+        for lt in vehicle {
+            assert!(lt.is_for_moving_vehicles());
+        }
+    }
+
+    #[test]
+    fn test_lane_markings() {
+        use super::{LaneMarkings, LaneType};
+
+        let expected = vec![
+            (LaneType::Driving, LaneMarkings::LaneLines),
+            (LaneType::Parking, LaneMarkings::ParkingSpots),
+            (LaneType::Sidewalk, LaneMarkings::SidewalkTiles),
+            (LaneType::Biking, LaneMarkings::Nothing),
+            (LaneType::Bus, LaneMarkings::LaneLines),
+            (LaneType::SharedLeftTurn, LaneMarkings::CenterTurnLines),
+            (LaneType::Construction, LaneMarkings::Nothing),
+            (LaneType::Shoulder, LaneMarkings::Nothing),
+            (LaneType::Buffer, LaneMarkings::Nothing),
+            (LaneType::Median, LaneMarkings::Nothing),
+        ];
+        // Also catches all() missing or repeating a type
+        assert_eq!(
+            LaneType::all(),
+            expected.iter().map(|(lt, _)| *lt).collect::<Vec<_>>()
+        );
+        for (lt, markings) in expected {
+            assert_eq!(markings, lt.markings(), "{:?}", lt);
+        }
+    }
+}
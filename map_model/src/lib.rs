@@ -10,6 +10,7 @@ mod make;
 mod map;
 pub mod osm;
 mod parking_lot;
+pub mod parse_units;
 mod pathfind;
 pub mod raw;
 mod road;
@@ -26,8 +27,14 @@ pub use crate::edits::{
     EditCmd, EditEffects, EditIntersection, MapEdits, OriginalLane, PermanentMapEdits,
 };
 pub use crate::intersection::{Intersection, IntersectionID, IntersectionType};
-pub use crate::lane::{Lane, LaneID, LaneType, PARKING_LOT_SPOT_LENGTH, PARKING_SPOT_LENGTH};
+pub use crate::lane::{
+    Lane, LaneID, LaneMarkings, LaneType, ParkingCapacity, ParkingMode, PARKING_LOT_SPOT_LENGTH,
+    PARKING_SPOT_LENGTH,
+};
+// Everything about interpreting a way's OSM tags as lanes, for tools (like the map_editor) that
+// work with RawMaps, not full Maps.
 pub use crate::make::initial::lane_specs::RoadSpec;
+pub use crate::make::initial::{lane_details, lane_specs, turn_lanes};
 pub use crate::map::Map;
 pub use crate::parking_lot::{ParkingLot, ParkingLotID};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGroup};
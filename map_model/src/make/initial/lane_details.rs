@@ -0,0 +1,1885 @@
+use super::lane_specs::{
+    default_lane_width, has_parking, is_bike_crossing, is_oneway, is_reversed_oneway, is_unmarked,
+    lane_types, osm_lane_numbering, shared_use_path, LaneInferencePolicy,
+};
+use super::turn_lanes::{parse_turn_lanes, repair_per_lane, repair_turn_lanes, TurnIndication};
+use crate::{osm, LaneType, PARKING_SPOT_LENGTH};
+use abstutil::Warn;
+use geom::Distance;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Details about a lane that don't change its LaneType, but that something downstream might care
+// about.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LaneDetails {
+    pub lt: LaneType,
+    // Only for Biking lanes. Advisory (usually dashed) bike lanes may be entered by cars when the
+    // road is too narrow; exclusive lanes may not.
+    pub advisory: bool,
+    // Only for Biking lanes.
+    pub protection: BikeProtection,
+    // Only for Biking lanes. How practical is the surface to ride on, from 0 (impassable) to 1
+    // (smooth)? The router can penalize rough lanes.
+    pub bike_usability: f64,
+    // Only for Biking lanes. Do cyclists ride both ways in this one lane?
+    pub bidirectional: bool,
+    // Only for Driving lanes.
+    pub access: Access,
+    // Only for Driving lanes. Overrides access at certain times.
+    pub access_conditions: Vec<ConditionalAccess>,
+    // Only for Driving lanes. Buses explicitly may use the lane, even if access is otherwise
+    // restricted.
+    pub buses_allowed: bool,
+    // Only for Driving lanes.
+    pub managed: Option<ManagedLane>,
+    // Only for Driving lanes. On a road without lane markings, this lane is really the whole
+    // shared space for one direction, so it's probably wider than usual.
+    pub unmarked: bool,
+    // Only for Driving and Bus lanes. Empty when there's no turn:lanes data.
+    pub turns: Vec<TurnIndication>,
+    // Only for Driving and Bus lanes. May vehicles here pass others, crossing into the opposite
+    // direction if necessary?
+    pub overtaking: bool,
+    // Only for Driving and Bus lanes. May heavy goods vehicles use this lane?
+    pub hgv_allowed: bool,
+    // Only for Driving and Bus lanes. May light commercial vehicles, like delivery vans, use this
+    // lane?
+    pub goods_allowed: bool,
+    // Only for Driving and Bus lanes. Something along the road slowing vehicles down.
+    pub traffic_calming: Option<TrafficCalming>,
+    // Only for Bus lanes. None means always reserved for buses. Otherwise, this is the OSM
+    // opening_hours condition (like "Mo-Fr 07:00-09:00") for when it's reserved; general traffic
+    // may use it the rest of the time.
+    pub bus_only_when: Option<String>,
+    // Only for Sidewalk lanes, and Biking lanes on a cycleway=crossing. Set when the way is tagged
+    // as part of a crossing.
+    pub crossing: Option<CrossingKind>,
+    // Only for Sidewalk lanes. Inside a building, so maybe not worth drawing outdoors.
+    pub indoor: bool,
+    // Only for Sidewalk lanes. From oneway:foot, people may only walk in this lane's direction.
+    // Normally they walk either way.
+    pub oneway: bool,
+    // Only for Sidewalk lanes.
+    pub surface: SidewalkSurface,
+    // Only for Sidewalk and Shoulder lanes. A shared-use path lets cyclists ride on the
+    // "sidewalk," and some regions let cyclists use the shoulder of motorways.
+    pub bikes_allowed: bool,
+    // Only for Parking lanes.
+    pub parking_condition: ParkingCondition,
+    // Only for Parking lanes.
+    pub parking_orientation: ParkingOrientation,
+    // Only for Parking lanes.
+    pub parking_placement: ParkingPlacement,
+    // Only for Parking lanes. How many spots are reserved for people with disabilities?
+    pub disabled_spots: usize,
+}
+
+impl LaneDetails {
+    pub fn new(lt: LaneType) -> LaneDetails {
+        LaneDetails {
+            lt,
+            advisory: false,
+            protection: BikeProtection::None,
+            bike_usability: 1.0,
+            bidirectional: false,
+            access: Access::Public,
+            access_conditions: Vec::new(),
+            buses_allowed: false,
+            managed: None,
+            unmarked: false,
+            turns: Vec::new(),
+            overtaking: true,
+            hgv_allowed: true,
+            goods_allowed: true,
+            traffic_calming: None,
+            bus_only_when: None,
+            crossing: None,
+            indoor: false,
+            oneway: false,
+            surface: SidewalkSurface::Paved,
+            bikes_allowed: false,
+            parking_condition: ParkingCondition::Free,
+            parking_orientation: ParkingOrientation::Parallel,
+            parking_placement: ParkingPlacement::OnStreet,
+            disabled_spots: 0,
+        }
+    }
+
+    // A bike lane carrying both directions only has room for about half as many cyclists going
+    // each way, and they conflict head-on. Scales the capacity of a one-way lane.
+    pub fn bike_capacity_factor(&self) -> f64 {
+        assert_eq!(self.lt, LaneType::Biking);
+        if self.bidirectional {
+            0.5
+        } else {
+            1.0
+        }
+    }
+
+    // How much of the roadway's width does this parking lane use?
+    pub fn parking_width_on_street(&self) -> Distance {
+        assert_eq!(self.lt, LaneType::Parking);
+        default_lane_width(self.lt) * self.parking_placement.fraction_on_street()
+    }
+
+    // How many cars fit along this parking lane? Like Lane::number_parking_spots, there are no
+    // spots right next to intersections.
+    pub fn number_parking_spots(&self, length: Distance) -> usize {
+        assert_eq!(self.lt, LaneType::Parking);
+        let spots =
+            ((length - PARKING_SPOT_LENGTH * 2.0) / self.parking_orientation.spot_length()).floor();
+        if spots >= 1.0 {
+            spots as usize
+        } else {
+            0
+        }
+    }
+
+    // Does this sidewalk lead pedestrians across a roadway?
+    pub fn connects_to_crossing(&self) -> bool {
+        self.lt == LaneType::Sidewalk && self.crossing.is_some()
+    }
+}
+
+// How pedestrians get across the roadway, which matters for timing pedestrian signals.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CrossingKind {
+    // Pedestrians get their own signal phase
+    Signalized,
+    // Zebra stripes or other markings, but no signal
+    Marked,
+    Unmarked,
+}
+
+impl CrossingKind {
+    // https://wiki.openstreetmap.org/wiki/Key:crossing
+    fn parse(osm_tags: &BTreeMap<String, String>) -> Option<CrossingKind> {
+        match osm_tags.get("crossing").map(|x| x.as_str()) {
+            Some("no") => None,
+            Some("traffic_signals") => Some(CrossingKind::Signalized),
+            Some("unmarked") => Some(CrossingKind::Unmarked),
+            Some(_) => Some(CrossingKind::Marked),
+            // Without details, don't assume anything helps pedestrians across. sidewalk=crossing
+            // isn't documented, but sometimes shows up on footways instead of footway=crossing.
+            None if osm::has_value(osm_tags, "footway", "crossing")
+                || (osm::has_value(osm_tags, osm::HIGHWAY, "footway")
+                    && osm::has_value(osm_tags, osm::SIDEWALK, "crossing"))
+                || is_bike_crossing(osm_tags) =>
+            {
+                Some(CrossingKind::Unmarked)
+            }
+            None => None,
+        }
+    }
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:surface, as tagged on sidewalks
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SidewalkSurface {
+    // Also used when nothing's mapped
+    Paved,
+    PavingStones,
+    Cobblestone,
+    Gravel,
+    Unpaved,
+}
+
+impl SidewalkSurface {
+    // side is "left" or "right"
+    fn parse(osm_tags: &BTreeMap<String, String>, side: &str) -> SidewalkSurface {
+        match osm_tags
+            .get(&format!("sidewalk:{}:surface", side))
+            .or_else(|| osm_tags.get("sidewalk:both:surface"))
+            .or_else(|| osm_tags.get("sidewalk:surface"))
+            .map(|x| x.as_str())
+        {
+            Some("paving_stones") => SidewalkSurface::PavingStones,
+            Some("sett") | Some("cobblestone") | Some("unhewn_cobblestone") => {
+                SidewalkSurface::Cobblestone
+            }
+            Some("gravel") | Some("fine_gravel") | Some("compacted") | Some("pebblestone") => {
+                SidewalkSurface::Gravel
+            }
+            Some("unpaved") | Some("ground") | Some("dirt") | Some("grass") | Some("sand")
+            | Some("mud") => SidewalkSurface::Unpaved,
+            _ => SidewalkSurface::Paved,
+        }
+    }
+
+    // Scales the default pedestrian speed. Nothing uses this yet.
+    pub fn walk_speed_multiplier(self) -> f64 {
+        match self {
+            SidewalkSurface::Paved | SidewalkSurface::PavingStones => 1.0,
+            SidewalkSurface::Cobblestone => 0.9,
+            SidewalkSurface::Gravel => 0.85,
+            SidewalkSurface::Unpaved => 0.75,
+        }
+    }
+}
+
+// Driving lanes operated differently from general traffic lanes
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ManagedLane {
+    // The direction of travel changes during the day
+    Reversible,
+    // High-occupancy vehicles ride free, everyone else pays a toll
+    HighOccupancyToll,
+    // Fewer entrances and exits than the parallel general lanes
+    Express,
+}
+
+impl ManagedLane {
+    fn parse(osm_tags: &BTreeMap<String, String>) -> Option<ManagedLane> {
+        let is = |key: &str, value: &str| osm::has_value(osm_tags, key, value);
+        if is("oneway", "reversible") {
+            Some(ManagedLane::Reversible)
+        } else if is("hov", "designated") && is("toll", "yes") {
+            Some(ManagedLane::HighOccupancyToll)
+        } else if is("express", "yes") {
+            Some(ManagedLane::Express)
+        } else {
+            None
+        }
+    }
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:traffic_calming
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TrafficCalming {
+    Bump,
+    Hump,
+    Cushion,
+    Table,
+    Chicane,
+    Choker,
+    RumbleStrip,
+    // Tagged, but not with a specific kind
+    Other,
+}
+
+impl TrafficCalming {
+    fn parse(osm_tags: &BTreeMap<String, String>) -> Option<TrafficCalming> {
+        match osm_tags.get("traffic_calming").map(|x| x.as_str()) {
+            None | Some("no") => None,
+            Some("bump") => Some(TrafficCalming::Bump),
+            Some("hump") => Some(TrafficCalming::Hump),
+            Some("cushion") => Some(TrafficCalming::Cushion),
+            Some("table") => Some(TrafficCalming::Table),
+            Some("chicane") => Some(TrafficCalming::Chicane),
+            Some("choker") => Some(TrafficCalming::Choker),
+            Some("rumble_strip") => Some(TrafficCalming::RumbleStrip),
+            Some(_) => Some(TrafficCalming::Other),
+        }
+    }
+}
+
+// Who may drive somewhere. Anything besides Public is still drivable, but the pathfinder should
+// avoid using it for through traffic.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Access {
+    Public,
+    // Only to reach something along the road
+    Destination,
+    // Only to visit the businesses along the road
+    Customers,
+    // Only with the owner's permission
+    Private,
+    // Closed to everyone except emergency vehicles
+    Emergency,
+}
+
+// https://wiki.openstreetmap.org/wiki/Conditional_restrictions, like "no @ (Mo-Fr 07:00-19:00)"
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConditionalAccess {
+    // The usual access values, like "no" or "destination"
+    pub access: String,
+    // An opening_hours-style condition, like "Mo-Fr 07:00-19:00"
+    pub when: String,
+}
+
+impl ConditionalAccess {
+    // Several restrictions may be separated by semicolons, which can also appear inside the
+    // parenthesized conditions. None if anything's malformed.
+    fn parse_list(value: &str) -> Option<Vec<ConditionalAccess>> {
+        let mut parts = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (idx, c) in value.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ';' if depth == 0 => {
+                    parts.push(&value[start..idx]);
+                    start = idx + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(&value[start..]);
+
+        let mut result = Vec::new();
+        for part in parts {
+            let mut split = part.splitn(2, '@');
+            let access = split.next()?.trim();
+            let when = split
+                .next()?
+                .trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .trim();
+            if access.is_empty() || when.is_empty() {
+                return None;
+            }
+            result.push(ConditionalAccess {
+                access: access.to_string(),
+                when: when.to_string(),
+            });
+        }
+        Some(result)
+    }
+}
+
+// What physically separates a bike lane from traffic?
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BikeProtection {
+    None,
+    // Solid lines or a painted buffer
+    Paint,
+    // Flexible posts, bollards, or similar
+    FlexPost,
+    Curb,
+    // A lane of parked cars sits between the bike lane and traffic
+    ParkingProtected,
+}
+
+impl BikeProtection {
+    // https://wiki.openstreetmap.org/wiki/Proposed_features/separation. side is "left" or "right".
+    fn parse(osm_tags: &BTreeMap<String, String>, side: &str) -> BikeProtection {
+        let separation = osm_tags
+            .get(&format!("cycleway:{}:separation", side))
+            .or_else(|| osm_tags.get("cycleway:both:separation"))
+            .or_else(|| osm_tags.get("cycleway:separation"))
+            .or_else(|| osm_tags.get("separation"));
+        match separation.map(|x| x.as_str()) {
+            Some("flex_post") | Some("bollard") | Some("vertical_panel") => {
+                return BikeProtection::FlexPost;
+            }
+            Some("kerb") | Some("curb") | Some("bump") => {
+                return BikeProtection::Curb;
+            }
+            Some("parking_lane") => {
+                return BikeProtection::ParkingProtected;
+            }
+            Some("solid_line") | Some("dashed_line") | Some("buffer") => {
+                return BikeProtection::Paint;
+            }
+            _ => {}
+        }
+
+        // A painted buffer, either yes or its width
+        match osm_tags
+            .get(&format!("cycleway:{}:buffer", side))
+            .or_else(|| osm_tags.get("cycleway:buffer"))
+            .map(|x| x.as_str())
+        {
+            None | Some("no") | Some("none") => BikeProtection::None,
+            Some(_) => BikeProtection::Paint,
+        }
+    }
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:smoothness and
+// https://wiki.openstreetmap.org/wiki/Key:surface. When both are tagged, the worse one wins. A bike
+// lane's own surface (like cycleway:right:surface) overrides the carriageway's. side is "left" or
+// "right".
+fn bike_usability(osm_tags: &BTreeMap<String, String>, side: &str) -> f64 {
+    let smoothness: f64 = match osm_tags.get("smoothness").map(|x| x.as_str()) {
+        Some("intermediate") => 0.8,
+        Some("bad") => 0.5,
+        Some("very_bad") => 0.3,
+        Some("horrible") => 0.1,
+        Some("very_horrible") | Some("impassable") => 0.0,
+        // excellent, good, or untagged
+        _ => 1.0,
+    };
+    let surface = match osm_tags
+        .get(&format!("cycleway:{}:surface", side))
+        .or_else(|| osm_tags.get("cycleway:both:surface"))
+        .or_else(|| osm_tags.get("cycleway:surface"))
+        .or_else(|| osm_tags.get("surface"))
+        .map(|x| x.as_str())
+    {
+        Some("compacted") | Some("fine_gravel") => 0.8,
+        Some("sett") | Some("cobblestone") | Some("unhewn_cobblestone") => 0.6,
+        Some("unpaved") | Some("gravel") | Some("pebblestone") | Some("ground") | Some("dirt") => {
+            0.5
+        }
+        Some("grass") | Some("sand") | Some("mud") => 0.3,
+        _ => 1.0,
+    };
+    smoothness.min(surface)
+}
+
+// side is "left" or "right". The sided tag wins, unless it doesn't describe a parking lane.
+fn parking_lane_value<'a>(
+    osm_tags: &'a BTreeMap<String, String>,
+    side: &str,
+) -> Option<&'a String> {
+    let sided = osm_tags.get(&format!("parking:lane:{}", side));
+    if has_parking(sided) {
+        sided
+    } else {
+        osm_tags
+            .get(osm::PARKING_BOTH)
+            .filter(|x| has_parking(Some(x)))
+    }
+}
+
+// How cars are lined up. Angled parking fits more cars into the same length of curb.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ParkingOrientation {
+    // Also used when nothing's specified
+    Parallel,
+    Diagonal,
+    Perpendicular,
+}
+
+impl ParkingOrientation {
+    fn parse(osm_tags: &BTreeMap<String, String>, side: &str) -> ParkingOrientation {
+        match parking_lane_value(osm_tags, side).map(|x| x.as_str()) {
+            Some("diagonal") => ParkingOrientation::Diagonal,
+            Some("perpendicular") => ParkingOrientation::Perpendicular,
+            _ => ParkingOrientation::Parallel,
+        }
+    }
+
+    // How much curb does one car take up?
+    pub fn spot_length(self) -> Distance {
+        match self {
+            ParkingOrientation::Parallel => PARKING_SPOT_LENGTH,
+            ParkingOrientation::Diagonal => Distance::meters(3.5),
+            ParkingOrientation::Perpendicular => Distance::meters(2.5),
+        }
+    }
+}
+
+// Where parked cars sit relative to the kerb
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ParkingPlacement {
+    // Also used when nothing's specified
+    OnStreet,
+    HalfOnKerb,
+    OnKerb,
+    Shoulder,
+}
+
+impl ParkingPlacement {
+    // Either like parking:lane:left=half_on_kerb, or the more detailed
+    // parking:lane:left=parallel + parking:lane:left:parallel=half_on_kerb
+    fn parse(osm_tags: &BTreeMap<String, String>, side: &str) -> ParkingPlacement {
+        let value = match parking_lane_value(osm_tags, side) {
+            Some(x) => x,
+            None => {
+                return ParkingPlacement::OnStreet;
+            }
+        };
+        let detail = osm_tags
+            .get(&format!("parking:lane:{}:{}", side, value))
+            .or_else(|| osm_tags.get(&format!("parking:lane:both:{}", value)))
+            .unwrap_or(value);
+        match detail.as_str() {
+            "half_on_kerb" => ParkingPlacement::HalfOnKerb,
+            "on_kerb" => ParkingPlacement::OnKerb,
+            "shoulder" => ParkingPlacement::Shoulder,
+            _ => ParkingPlacement::OnStreet,
+        }
+    }
+
+    // How much of the parking lane's width takes space from the roadway? The rest is on the
+    // sidewalk.
+    pub fn fraction_on_street(self) -> f64 {
+        match self {
+            ParkingPlacement::OnStreet | ParkingPlacement::Shoulder => 1.0,
+            ParkingPlacement::HalfOnKerb => 0.5,
+            ParkingPlacement::OnKerb => 0.0,
+        }
+    }
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:parking:condition
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ParkingCondition {
+    // Also used when nothing's mapped
+    Free,
+    Paid,
+    ResidentsOnly,
+    TimeLimited,
+}
+
+impl ParkingCondition {
+    // side is "left" or "right"
+    fn parse(osm_tags: &BTreeMap<String, String>, side: &str) -> ParkingCondition {
+        let get = |suffix: &str| {
+            osm_tags
+                .get(&format!("parking:condition:{}{}", side, suffix))
+                .or_else(|| osm_tags.get(&format!("parking:condition:both{}", suffix)))
+        };
+        let fee = osm_tags
+            .get(&format!("parking:lane:{}:fee", side))
+            .or_else(|| osm_tags.get("parking:lane:both:fee"));
+
+        match get("").map(|x| x.as_str()) {
+            Some("ticket") | Some("fee") => ParkingCondition::Paid,
+            Some("residents") => ParkingCondition::ResidentsOnly,
+            Some("disc") => ParkingCondition::TimeLimited,
+            _ => {
+                if fee.map(|x| x.as_str()) == Some(osm::YES) {
+                    ParkingCondition::Paid
+                } else if get(":maxstay").is_some() {
+                    ParkingCondition::TimeLimited
+                } else {
+                    ParkingCondition::Free
+                }
+            }
+        }
+    }
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:capacity:disabled. side is "left" or "right".
+fn disabled_parking_spots(osm_tags: &BTreeMap<String, String>, side: &str) -> usize {
+    let get = |suffix: &str| {
+        osm_tags
+            .get(&format!("parking:lane:{}:{}", side, suffix))
+            .or_else(|| osm_tags.get(&format!("parking:lane:both:{}", suffix)))
+    };
+    match get("capacity:disabled").map(|x| x.as_str()) {
+        Some("no") | None => {}
+        Some(x) => {
+            // "yes" just means there are some
+            return x.parse::<usize>().unwrap_or(1);
+        }
+    }
+    // The whole segment is reserved
+    if get("disabled")
+        .or_else(|| osm_tags.get("disabled"))
+        .map(|x| x.as_str())
+        == Some("designated")
+    {
+        return get("capacity")
+            .and_then(|x| x.parse::<usize>().ok())
+            .unwrap_or(1);
+    }
+    0
+}
+
+// Like get_lane_types, but also figures out the details of each lane.
+// (original direction, reversed direction)
+pub fn get_lane_details(
+    osm_tags: &BTreeMap<String, String>,
+) -> (Vec<LaneDetails>, Vec<LaneDetails>) {
+    lane_details(osm_tags, &mut Vec::new())
+}
+
+// Like get_lane_details, but also complains about contradictory or malformed tags.
+pub fn get_lane_details_warn(
+    osm_tags: &BTreeMap<String, String>,
+) -> Warn<(Vec<LaneDetails>, Vec<LaneDetails>)> {
+    let mut warnings = Vec::new();
+    let result = lane_details(osm_tags, &mut warnings);
+    Warn::warnings(result, warnings)
+}
+
+fn lane_details(
+    osm_tags: &BTreeMap<String, String>,
+    warnings: &mut Vec<String>,
+) -> (Vec<LaneDetails>, Vec<LaneDetails>) {
+    let (fwd_types, back_types) = lane_types(osm_tags, &LaneInferencePolicy::default(), warnings);
+    let mut fwd_side: Vec<LaneDetails> = fwd_types.into_iter().map(LaneDetails::new).collect();
+    let mut back_side: Vec<LaneDetails> = back_types.into_iter().map(LaneDetails::new).collect();
+
+    // https://wiki.openstreetmap.org/wiki/Key:cycleway:lane. The sided tags take precedence.
+    let advisory = |side: &str| {
+        osm_tags
+            .get(&format!("cycleway:{}:lane", side))
+            .or_else(|| osm_tags.get("cycleway:lane"))
+            .map(|x| x == "advisory")
+            .unwrap_or(false)
+    };
+    // https://wiki.openstreetmap.org/wiki/Key:cycleway:right:oneway
+    let bidirectional = |side: &str| {
+        osm_tags
+            .get(&format!("cycleway:{}:oneway", side))
+            .or_else(|| osm_tags.get("cycleway:both:oneway"))
+            .or_else(|| osm_tags.get("cycleway:oneway"))
+            .map(|x| x.as_str())
+            == Some(osm::NO)
+    };
+    set_by_side(
+        &mut fwd_side,
+        &mut back_side,
+        LaneType::Biking,
+        |l, side| {
+            l.advisory = advisory(side);
+            l.protection = BikeProtection::parse(osm_tags, side);
+            l.bidirectional = bidirectional(side);
+            l.bike_usability = bike_usability(osm_tags, side);
+        },
+    );
+
+    let access = match osm_tags
+        .get("motor_vehicle")
+        .or_else(|| osm_tags.get("access"))
+        .map(|x| x.as_str())
+    {
+        Some("destination") => Access::Destination,
+        Some("customers") => Access::Customers,
+        Some("private") => Access::Private,
+        // https://wiki.openstreetmap.org/wiki/Key:emergency
+        Some("no")
+            if matches!(
+                osm_tags.get("emergency").map(|x| x.as_str()),
+                Some("yes") | Some("designated")
+            ) =>
+        {
+            Access::Emergency
+        }
+        // https://wiki.openstreetmap.org/wiki/Tag:highway=raceway
+        _ if osm::has_value(osm_tags, osm::HIGHWAY, "raceway") => Access::Private,
+        _ => Access::Public,
+    };
+    // The more specific tag wins.
+    let buses_allowed = matches!(
+        osm_tags
+            .get("bus")
+            .or_else(|| osm_tags.get("psv"))
+            .map(|x| x.as_str()),
+        Some("yes") | Some("designated") | Some("permissive")
+    );
+    // Like access, the more specific tag wins.
+    let mut access_conditions = Vec::new();
+    for key in &["motor_vehicle:conditional", "access:conditional"] {
+        if let Some(value) = osm_tags.get(*key) {
+            if let Some(list) = ConditionalAccess::parse_list(value) {
+                access_conditions = list;
+                break;
+            }
+            warnings.push(format!("can't parse {}={}, ignoring it", key, value));
+        }
+    }
+    let managed = ManagedLane::parse(osm_tags);
+    for l in fwd_side.iter_mut().chain(back_side.iter_mut()) {
+        if l.lt == LaneType::Driving {
+            l.access = access;
+            l.access_conditions = access_conditions.clone();
+            l.buses_allowed = buses_allowed;
+            l.managed = managed;
+        }
+    }
+
+    set_by_side(
+        &mut fwd_side,
+        &mut back_side,
+        LaneType::Parking,
+        |l, side| {
+            l.parking_condition = ParkingCondition::parse(osm_tags, side);
+            l.parking_orientation = ParkingOrientation::parse(osm_tags, side);
+            l.parking_placement = ParkingPlacement::parse(osm_tags, side);
+            l.disabled_spots = disabled_parking_spots(osm_tags, side);
+        },
+    );
+
+    let crossing = CrossingKind::parse(osm_tags);
+    // https://wiki.openstreetmap.org/wiki/Tag:highway=corridor
+    let indoor = osm::has_value(osm_tags, osm::HIGHWAY, "corridor")
+        || osm::has_value(osm_tags, "indoor", osm::YES);
+    let bike_crossing = is_bike_crossing(osm_tags);
+    let foot_oneway = matches!(
+        osm_tags.get("oneway:foot").map(|x| x.as_str()),
+        Some("yes") | Some("-1")
+    );
+    for l in fwd_side.iter_mut().chain(back_side.iter_mut()) {
+        if l.lt == LaneType::Sidewalk {
+            l.crossing = crossing;
+            l.indoor = indoor;
+            l.oneway = foot_oneway;
+        }
+        if l.lt == LaneType::Biking && bike_crossing {
+            l.crossing = crossing;
+        }
+    }
+
+    set_by_side(
+        &mut fwd_side,
+        &mut back_side,
+        LaneType::Sidewalk,
+        |l, side| {
+            l.surface = SidewalkSurface::parse(osm_tags, side);
+        },
+    );
+
+    if shared_use_path(osm_tags) == Some(false) {
+        for l in &mut fwd_side {
+            if l.lt == LaneType::Sidewalk {
+                l.bikes_allowed = true;
+            }
+        }
+    }
+    let bikes_on_shoulder = matches!(
+        osm_tags
+            .get("shoulder:access:bicycle")
+            .or_else(|| osm_tags.get("bicycle"))
+            .map(|x| x.as_str()),
+        Some("yes") | Some("designated") | Some("permissive")
+    );
+    for l in fwd_side.iter_mut().chain(back_side.iter_mut()) {
+        if l.lt == LaneType::Shoulder {
+            l.bikes_allowed = bikes_on_shoulder;
+        }
+    }
+
+    // For keys that can be suffixed with :forward or :backward. The undirected tag is only
+    // meaningful on oneways, and applies to whichever side has the traffic.
+    let directed = |key: &str, fwd: bool| {
+        let suffix = if fwd { "forward" } else { "backward" };
+        osm_tags.get(&format!("{}:{}", key, suffix)).or_else(|| {
+            if is_oneway(osm_tags) && is_reversed_oneway(osm_tags) != fwd {
+                osm_tags.get(key)
+            } else {
+                None
+            }
+        })
+    };
+
+    // https://wiki.openstreetmap.org/wiki/Key:overtaking
+    let overtaking = |fwd: bool| {
+        let suffix = if fwd { "forward" } else { "backward" };
+        match osm_tags
+            .get(&format!("overtaking:{}", suffix))
+            .or_else(|| osm_tags.get("overtaking"))
+            .map(|x| x.as_str())
+        {
+            Some("no") => false,
+            Some("forward") => fwd,
+            Some("backward") => !fwd,
+            _ => true,
+        }
+    };
+    let set_overtaking = |l: &mut LaneDetails, x| l.overtaking = x;
+    apply_lane_flags(
+        &mut fwd_side,
+        overtaking(true),
+        "overtaking:lanes",
+        directed("overtaking:lanes", true),
+        set_overtaking,
+        warnings,
+    );
+    apply_lane_flags(
+        &mut back_side,
+        overtaking(false),
+        "overtaking:lanes",
+        directed("overtaking:lanes", false),
+        set_overtaking,
+        warnings,
+    );
+
+    let unmarked = is_unmarked(osm_tags);
+    for l in fwd_side.iter_mut().chain(back_side.iter_mut()) {
+        if l.lt == LaneType::Driving {
+            l.unmarked = unmarked;
+        }
+    }
+
+    let calming = TrafficCalming::parse(osm_tags);
+    for l in fwd_side.iter_mut().chain(back_side.iter_mut()) {
+        if l.lt == LaneType::Driving || l.lt == LaneType::Bus {
+            l.traffic_calming = calming;
+        }
+    }
+
+    // https://wiki.openstreetmap.org/wiki/Key:hgv and https://wiki.openstreetmap.org/wiki/Key:goods
+    // for lighter vehicles. Each is restricted independently.
+    let hgv = !osm::has_value(osm_tags, "hgv", osm::NO);
+    let set_hgv = |l: &mut LaneDetails, x| l.hgv_allowed = x;
+    apply_lane_flags(
+        &mut fwd_side,
+        hgv,
+        "hgv:lanes",
+        directed("hgv:lanes", true),
+        set_hgv,
+        warnings,
+    );
+    apply_lane_flags(
+        &mut back_side,
+        hgv,
+        "hgv:lanes",
+        directed("hgv:lanes", false),
+        set_hgv,
+        warnings,
+    );
+    let goods = !osm::has_value(osm_tags, "goods", osm::NO);
+    let set_goods = |l: &mut LaneDetails, x| l.goods_allowed = x;
+    apply_lane_flags(
+        &mut fwd_side,
+        goods,
+        "goods:lanes",
+        directed("goods:lanes", true),
+        set_goods,
+        warnings,
+    );
+    apply_lane_flags(
+        &mut back_side,
+        goods,
+        "goods:lanes",
+        directed("goods:lanes", false),
+        set_goods,
+        warnings,
+    );
+
+    // https://wiki.openstreetmap.org/wiki/Conditional_restrictions
+    if let Some(list) = directed("bus:lanes:conditional", true) {
+        apply_bus_conditions(&mut fwd_side, list, warnings);
+    }
+    if let Some(list) = directed("bus:lanes:conditional", false) {
+        apply_bus_conditions(&mut back_side, list, warnings);
+    }
+
+    // Each direction is listed left to right, which is the same order as our lanes.
+    if let Some(turns) = directed("turn:lanes", true).and_then(|x| parse_turn_lanes(x)) {
+        apply_turn_lanes(&mut fwd_side, turns, warnings);
+    }
+    if let Some(turns) = directed("turn:lanes", false).and_then(|x| parse_turn_lanes(x)) {
+        apply_turn_lanes(&mut back_side, turns, warnings);
+    }
+
+    (fwd_side, back_side)
+}
+
+fn details_numbering(side: &[LaneDetails]) -> Vec<usize> {
+    osm_lane_numbering(&side.iter().map(|l| l.lt).collect::<Vec<_>>())
+}
+
+// Updates every lane of one type on both sides. set also gets the name of the lane's physical side,
+// "right" for fwd and "left" for back, to look up sided tags like cycleway:right.
+fn set_by_side<F: FnMut(&mut LaneDetails, &str)>(
+    fwd_side: &mut [LaneDetails],
+    back_side: &mut [LaneDetails],
+    lt: LaneType,
+    mut set: F,
+) {
+    for (side, lanes) in [("right", fwd_side), ("left", back_side)].iter_mut() {
+        for l in lanes.iter_mut().filter(|l| l.lt == lt) {
+            set(l, side);
+        }
+    }
+}
+
+// per_lane is the value of key, something like "yes|no", overriding the default for each motor
+// vehicle lane. Like turn:lanes, a list that doesn't match the lane count is repaired with a warning.
+fn apply_lane_flags(
+    side: &mut [LaneDetails],
+    default: bool,
+    key: &str,
+    per_lane: Option<&String>,
+    set: fn(&mut LaneDetails, bool),
+    warnings: &mut Vec<String>,
+) {
+    let indices = details_numbering(side);
+    let per_lane: Vec<Option<bool>> = match per_lane {
+        Some(list) => {
+            let (list, warning) = repair_per_lane(
+                key,
+                list.split('|').map(|lane| Some(lane != "no")).collect(),
+                indices.len(),
+                None,
+            );
+            warnings.extend(warning);
+            list
+        }
+        None => vec![None; indices.len()],
+    };
+    for (idx, value) in indices.into_iter().zip(per_lane) {
+        set(&mut side[idx], value.unwrap_or(default));
+    }
+}
+
+// list is something like "|designated @ (Mo-Fr 07:00-09:00)"
+fn apply_bus_conditions(side: &mut [LaneDetails], list: &str, warnings: &mut Vec<String>) {
+    let indices = details_numbering(side);
+    let (values, warning) = repair_per_lane(
+        "bus:lanes:conditional",
+        list.split('|').collect(),
+        indices.len(),
+        "",
+    );
+    warnings.extend(warning);
+    for (idx, value) in indices.into_iter().zip(values) {
+        if side[idx].lt == LaneType::Bus {
+            side[idx].bus_only_when = parse_bus_condition(value);
+        }
+    }
+}
+
+// Of possibly many "value @ (condition)" pairs separated by semicolons, find when buses get the
+// lane.
+fn parse_bus_condition(value: &str) -> Option<String> {
+    for part in value.split(';') {
+        let mut split = part.splitn(2, '@');
+        let access = split.next()?.trim();
+        if let Some(condition) = split.next() {
+            if access == "designated" || access == "yes" {
+                let condition = condition
+                    .trim()
+                    .trim_start_matches('(')
+                    .trim_end_matches(')');
+                return Some(condition.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+fn apply_turn_lanes(
+    side: &mut [LaneDetails],
+    turns: Vec<Vec<TurnIndication>>,
+    warnings: &mut Vec<String>,
+) {
+    let indices = details_numbering(side);
+    let (turns, warning) = repair_turn_lanes(turns, indices.len());
+    warnings.extend(warning);
+    for (idx, turns) in indices.into_iter().zip(turns) {
+        side[idx].turns = turns;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        default_lane_width, get_lane_details, is_bike_crossing, lane_details, Access,
+        BikeProtection, ConditionalAccess, CrossingKind, ManagedLane, ParkingCondition,
+        ParkingOrientation, ParkingPlacement, SidewalkSurface, TrafficCalming, TurnIndication,
+    };
+    use crate::make::initial::lane_specs::get_lane_types;
+    use crate::{LaneType, RoadSpec};
+    use geom::Distance;
+    use std::collections::BTreeMap;
+
+    fn tags(kv: Vec<&str>) -> BTreeMap<String, String> {
+        let mut tags = BTreeMap::new();
+        for pair in kv {
+            let parts = pair.split('=').collect::<Vec<_>>();
+            tags.insert(parts[0].to_string(), parts[1].to_string());
+        }
+        tags
+    }
+
+    // Renders lanes in the same compact form RoadSpec uses, like "ddps"
+    fn lanes_to_string(lanes: &[LaneType]) -> String {
+        lanes.iter().map(|lt| RoadSpec::lt_to_char(*lt)).collect()
+    }
+
+    fn assert_lanes(expected: &str, actual: &[LaneType]) {
+        let actual = lanes_to_string(actual);
+        assert_eq!(
+            expected, actual,
+            "got {:?}, expected {:?}",
+            actual, expected
+        );
+    }
+
+    #[test]
+    fn test_bike_lane_protection() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "cycleway=lane",
+            "separation=flex_post",
+        ]));
+        assert_eq!(BikeProtection::FlexPost, fwd[1].protection);
+        assert_eq!(BikeProtection::FlexPost, back[1].protection);
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "cycleway:right=lane",
+            "cycleway:left=lane",
+            "cycleway:right:separation=parking_lane",
+            "cycleway:left:buffer=0.5",
+        ]));
+        assert_eq!(BikeProtection::ParkingProtected, fwd[1].protection);
+        assert_eq!(BikeProtection::Paint, back[1].protection);
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential", "cycleway=lane"]));
+        assert_eq!(BikeProtection::None, fwd[1].protection);
+    }
+
+    #[test]
+    fn test_bike_usability() {
+        let usability = |kv: Vec<&str>| {
+            let (fwd, _) = get_lane_details(&tags(kv));
+            fwd[1].bike_usability
+        };
+        let smooth = usability(vec!["highway=residential", "cycleway=lane"]);
+        assert_eq!(1.0, smooth);
+        let rough = usability(vec![
+            "highway=residential",
+            "cycleway=lane",
+            "smoothness=very_bad",
+        ]);
+        assert!(rough < smooth);
+        assert!(rough > 0.0);
+        assert_eq!(
+            0.0,
+            usability(vec![
+                "highway=residential",
+                "cycleway=lane",
+                "smoothness=impassable",
+            ])
+        );
+        // The worse of the two counts
+        assert!(
+            usability(vec![
+                "highway=residential",
+                "cycleway=lane",
+                "smoothness=good",
+                "surface=gravel",
+            ]) < smooth
+        );
+
+        // A smooth bike lane on a rough road
+        assert_eq!(
+            1.0,
+            usability(vec![
+                "highway=residential",
+                "cycleway=lane",
+                "surface=sett",
+                "cycleway:surface=asphalt",
+            ])
+        );
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "cycleway=lane",
+            "cycleway:right:surface=gravel",
+        ]));
+        assert_eq!(0.5, fwd[1].bike_usability);
+        assert_eq!(1.0, back[1].bike_usability);
+    }
+
+    #[test]
+    fn test_bidirectional_bike_lanes() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=secondary",
+            "cycleway:left=lane",
+            "cycleway:right=lane",
+            "cycleway:left:oneway=no",
+        ]));
+        let (left, right) = (&back[1], &fwd[1]);
+        assert_eq!(LaneType::Biking, left.lt);
+        assert!(left.bidirectional);
+        assert_eq!(0.5, left.bike_capacity_factor());
+        assert_eq!(LaneType::Biking, right.lt);
+        assert!(!right.bidirectional);
+        assert_eq!(1.0, right.bike_capacity_factor());
+    }
+
+    #[test]
+    fn test_advisory_bike_lanes() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "cycleway=lane",
+            "cycleway:lane=advisory",
+        ]));
+        assert!(fwd[1].lt == LaneType::Biking && fwd[1].advisory);
+        assert!(back[1].lt == LaneType::Biking && back[1].advisory);
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "cycleway=lane",
+            "cycleway:lane=exclusive",
+        ]));
+        assert!(fwd[1].lt == LaneType::Biking && !fwd[1].advisory);
+        assert!(back[1].lt == LaneType::Biking && !back[1].advisory);
+
+        // Default to exclusive, and let one side differ
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "cycleway=lane",
+            "cycleway:left:lane=advisory",
+        ]));
+        assert!(!fwd[1].advisory);
+        assert!(back[1].advisory);
+    }
+
+    #[test]
+    fn test_destination_access() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "access=destination",
+            "sidewalk=both",
+        ]));
+        assert_eq!(LaneType::Driving, fwd[0].lt);
+        assert_eq!(Access::Destination, fwd[0].access);
+        assert_eq!(Access::Destination, back[0].access);
+        // Only the driving lanes are restricted
+        assert_eq!(Access::Public, fwd[1].access);
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=service", "access=customers"]));
+        assert_eq!(Access::Customers, fwd[0].access);
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential"]));
+        assert_eq!(Access::Public, fwd[0].access);
+    }
+
+    #[test]
+    fn test_conditional_access() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "motor_vehicle:conditional=no @ (Mo-Fr 07:00-19:00)",
+            "sidewalk=both",
+        ]));
+        let expected = vec![ConditionalAccess {
+            access: "no".to_string(),
+            when: "Mo-Fr 07:00-19:00".to_string(),
+        }];
+        assert_eq!(expected, fwd[0].access_conditions);
+        assert_eq!(expected, back[0].access_conditions);
+        assert!(fwd[1].access_conditions.is_empty());
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "access:conditional=destination @ (07:00-09:00; 16:00-18:00); no @ snow",
+        ]));
+        assert_eq!(
+            vec![
+                ConditionalAccess {
+                    access: "destination".to_string(),
+                    when: "07:00-09:00; 16:00-18:00".to_string(),
+                },
+                ConditionalAccess {
+                    access: "no".to_string(),
+                    when: "snow".to_string(),
+                },
+            ],
+            fwd[0].access_conditions
+        );
+
+        let mut warnings = Vec::new();
+        let (fwd, _) = lane_details(
+            &tags(vec!["highway=residential", "access:conditional=sometimes"]),
+            &mut warnings,
+        );
+        assert_eq!(1, warnings.len());
+        assert!(fwd[0].access_conditions.is_empty());
+    }
+
+    #[test]
+    fn test_buses_allowed() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "motor_vehicle=destination",
+            "psv=yes",
+            "sidewalk=right",
+        ]));
+        assert_eq!(LaneType::Driving, fwd[0].lt);
+        assert!(fwd[0].buses_allowed);
+        assert!(back[0].buses_allowed);
+        assert!(!fwd[1].buses_allowed);
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential", "psv=yes", "bus=no"]));
+        assert!(!fwd[0].buses_allowed);
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential"]));
+        assert!(!fwd[0].buses_allowed);
+    }
+
+    #[test]
+    fn test_managed_lanes() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=motorway",
+            "oneway=reversible",
+            "lanes=2",
+            "lanes:both_ways=2",
+        ]));
+        assert_eq!(2, fwd.len());
+        assert!(back.is_empty());
+        for l in &fwd {
+            assert_eq!(LaneType::Driving, l.lt);
+            assert_eq!(Some(ManagedLane::Reversible), l.managed);
+        }
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=motorway",
+            "oneway=yes",
+            "hov=designated",
+            "toll=yes",
+        ]));
+        assert_eq!(Some(ManagedLane::HighOccupancyToll), fwd[0].managed);
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=primary",
+            "lanes=3",
+            "lanes:both_ways=1",
+        ]));
+        assert_lanes("ld", &fwd.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert_eq!(None, fwd[0].managed);
+        assert_eq!(None, fwd[1].managed);
+    }
+
+    #[test]
+    fn test_unmarked_turn_lanes() {
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=primary",
+            "oneway=yes",
+            "lanes=3",
+            "turn:lanes=left||right",
+        ]));
+        assert_eq!(vec![TurnIndication::Left], fwd[0].turns);
+        assert_eq!(vec![TurnIndication::None], fwd[1].turns);
+        assert_eq!(vec![TurnIndication::Right], fwd[2].turns);
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=primary",
+            "oneway=yes",
+            "lanes=2",
+            "turn:lanes=left;through|none",
+        ]));
+        assert_eq!(
+            vec![TurnIndication::Left, TurnIndication::Through],
+            fwd[0].turns
+        );
+        assert_eq!(vec![TurnIndication::None], fwd[1].turns);
+    }
+
+    #[test]
+    fn test_mismatched_turn_lanes() {
+        let mut warnings = Vec::new();
+        let (fwd, _) = lane_details(
+            &tags(vec![
+                "highway=primary",
+                "oneway=yes",
+                "lanes=2",
+                "turn:lanes=left|through|right",
+            ]),
+            &mut warnings,
+        );
+        assert_eq!(vec![TurnIndication::Left], fwd[0].turns);
+        assert_eq!(vec![TurnIndication::Through], fwd[1].turns);
+        assert_eq!(1, warnings.len());
+
+        let mut warnings = Vec::new();
+        let (fwd, _) = lane_details(
+            &tags(vec![
+                "highway=primary",
+                "oneway=yes",
+                "lanes=3",
+                "turn:lanes=left",
+            ]),
+            &mut warnings,
+        );
+        assert_eq!(vec![TurnIndication::Left], fwd[0].turns);
+        assert_eq!(vec![TurnIndication::None], fwd[1].turns);
+        assert_eq!(vec![TurnIndication::None], fwd[2].turns);
+        assert_eq!(1, warnings.len());
+    }
+
+    #[test]
+    fn test_directional_turn_lanes() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=primary",
+            "lanes=5",
+            "lanes:forward=3",
+            "lanes:backward=2",
+            "turn:lanes:forward=left|through|through;right",
+            "turn:lanes:backward=left;through|right",
+            "turn:lanes=ignored",
+        ]));
+        assert_eq!(vec![TurnIndication::Left], fwd[0].turns);
+        assert_eq!(vec![TurnIndication::Through], fwd[1].turns);
+        assert_eq!(
+            vec![TurnIndication::Through, TurnIndication::Right],
+            fwd[2].turns
+        );
+        assert_eq!(
+            vec![TurnIndication::Left, TurnIndication::Through],
+            back[0].turns
+        );
+        assert_eq!(vec![TurnIndication::Right], back[1].turns);
+
+        // The undirected tag doesn't say which direction it's for
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=primary",
+            "lanes=2",
+            "turn:lanes=left|right",
+        ]));
+        assert!(fwd[0].turns.is_empty());
+        assert!(back[0].turns.is_empty());
+    }
+
+    #[test]
+    fn test_crossing_sidewalks() {
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=footway",
+            "footway=crossing",
+            "crossing=marked",
+        ]));
+        assert!(fwd[0].connects_to_crossing());
+
+        let (fwd, back) = get_lane_details(&tags(vec!["highway=residential", "sidewalk=both"]));
+        assert!(!fwd[1].connects_to_crossing());
+        assert!(!back[1].connects_to_crossing());
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=footway", "crossing=no"]));
+        assert!(!fwd[0].connects_to_crossing());
+
+        for (value, kind) in &[
+            ("traffic_signals", CrossingKind::Signalized),
+            ("zebra", CrossingKind::Marked),
+            ("uncontrolled", CrossingKind::Marked),
+            ("unmarked", CrossingKind::Unmarked),
+        ] {
+            let (fwd, _) = get_lane_details(&tags(vec![
+                "highway=footway",
+                "footway=crossing",
+                &format!("crossing={}", value),
+            ]));
+            assert_eq!(Some(*kind), fwd[0].crossing);
+        }
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=footway", "footway=crossing"]));
+        assert_eq!(Some(CrossingKind::Unmarked), fwd[0].crossing);
+        assert_eq!(LaneType::Sidewalk, fwd[0].lt);
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=footway", "sidewalk=crossing"]));
+        assert_eq!(Some(CrossingKind::Unmarked), fwd[0].crossing);
+        // On a road, that's not a crossing at all
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential", "sidewalk=crossing"]));
+        assert!(fwd.iter().all(|l| l.crossing.is_none()));
+    }
+
+    #[test]
+    fn test_bike_crossing() {
+        let (fwd, back) = get_lane_details(&tags(vec!["highway=cycleway", "cycleway=crossing"]));
+        assert_lanes("b", &fwd.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert_lanes("b", &back.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert_eq!(Some(CrossingKind::Unmarked), fwd[0].crossing);
+        assert_eq!(Some(CrossingKind::Unmarked), back[0].crossing);
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=cycleway",
+            "cycleway=crossing",
+            "crossing=traffic_signals",
+            "oneway=yes",
+        ]));
+        assert_lanes("b", &fwd.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert!(back.is_empty());
+        assert_eq!(Some(CrossingKind::Signalized), fwd[0].crossing);
+
+        // Bike lanes along a road aren't crossings
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential", "cycleway=lane"]));
+        assert!(fwd.iter().all(|l| l.crossing.is_none()));
+        assert!(!is_bike_crossing(&tags(vec![
+            "highway=residential",
+            "cycleway=crossing"
+        ])));
+    }
+
+    #[test]
+    fn test_oneway_footways() {
+        let (fwd, back) = get_lane_details(&tags(vec!["highway=footway"]));
+        assert!(!fwd[0].oneway);
+        assert!(back.is_empty());
+
+        let (fwd, back) = get_lane_details(&tags(vec!["highway=footway", "oneway:foot=yes"]));
+        assert!(fwd[0].oneway);
+        assert!(back.is_empty());
+
+        let (fwd, back) = get_lane_details(&tags(vec!["highway=footway", "oneway:foot=-1"]));
+        assert!(fwd.is_empty());
+        assert_lanes("s", &back.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert!(back[0].oneway);
+    }
+
+    #[test]
+    fn test_indoor_corridor() {
+        let (fwd, back) = get_lane_details(&tags(vec!["highway=corridor", "level=1"]));
+        assert_eq!(1, fwd.len());
+        assert!(back.is_empty());
+        assert_eq!(LaneType::Sidewalk, fwd[0].lt);
+        assert!(fwd[0].indoor);
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=footway"]));
+        assert!(!fwd[0].indoor);
+    }
+
+    #[test]
+    fn test_sidewalk_surface() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "sidewalk=both",
+            "sidewalk:both:surface=gravel",
+        ]));
+        for l in &[fwd.last().unwrap(), back.last().unwrap()] {
+            assert_eq!(LaneType::Sidewalk, l.lt);
+            assert_eq!(SidewalkSurface::Gravel, l.surface);
+            assert!(l.surface.walk_speed_multiplier() < 1.0);
+        }
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "sidewalk=both",
+            "sidewalk:left:surface=paving_stones",
+        ]));
+        assert_eq!(SidewalkSurface::Paved, fwd.last().unwrap().surface);
+        assert_eq!(SidewalkSurface::PavingStones, back.last().unwrap().surface);
+    }
+
+    #[test]
+    fn test_disabled_parking() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "parking:lane:both=parallel",
+            "parking:lane:right:capacity:disabled=2",
+        ]));
+        assert_eq!(LaneType::Parking, fwd[1].lt);
+        assert_eq!(2, fwd[1].disabled_spots);
+        assert_eq!(0, back[1].disabled_spots);
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "parking:lane:both=parallel",
+            "parking:lane:left:disabled=designated",
+            "parking:lane:left:capacity=4",
+        ]));
+        assert_eq!(0, fwd[1].disabled_spots);
+        assert_eq!(4, back[1].disabled_spots);
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "parking:lane:both=parallel",
+        ]));
+        assert_eq!(0, fwd[1].disabled_spots);
+    }
+
+    #[test]
+    fn test_shared_use_path() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=path",
+            "foot=designated",
+            "bicycle=designated",
+            "segregated=no",
+        ]));
+        assert_eq!(1, fwd.len());
+        assert!(back.is_empty());
+        assert_eq!(LaneType::Sidewalk, fwd[0].lt);
+        assert!(fwd[0].bikes_allowed);
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=path",
+            "foot=designated",
+            "bicycle=designated",
+            "segregated=yes",
+        ]));
+        assert_eq!(
+            vec![LaneType::Biking, LaneType::Sidewalk],
+            fwd.iter().map(|l| l.lt).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![LaneType::Biking],
+            back.iter().map(|l| l.lt).collect::<Vec<_>>()
+        );
+        assert!(!fwd[1].bikes_allowed);
+    }
+
+    #[test]
+    fn test_lane_markings_no() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "lane_markings=no",
+            "sidewalk=none",
+        ]));
+        assert_lanes("d", &fwd.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert_lanes("d", &back.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert!(fwd[0].unmarked);
+        assert!(back[0].unmarked);
+
+        // The lane count doesn't matter
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "lane_markings=no",
+            "lanes=4",
+            "sidewalk=both",
+        ]));
+        assert_lanes("ds", &fwd);
+        assert_lanes("ds", &back);
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "lanes=2",
+            "sidewalk=none",
+        ]));
+        assert!(!fwd[0].unmarked);
+    }
+
+    #[test]
+    fn test_parking_condition() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "parking:lane:both=parallel",
+            "parking:condition:left=ticket",
+        ]));
+        assert_eq!(LaneType::Parking, back[1].lt);
+        assert_eq!(ParkingCondition::Paid, back[1].parking_condition);
+        assert_eq!(ParkingCondition::Free, fwd[1].parking_condition);
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "parking:lane:both=parallel",
+            "parking:condition:both=residents",
+            "parking:condition:right:maxstay=2 h",
+            "parking:condition:right=free",
+        ]));
+        assert_eq!(ParkingCondition::TimeLimited, fwd[1].parking_condition);
+        assert_eq!(ParkingCondition::ResidentsOnly, back[1].parking_condition);
+    }
+
+    #[test]
+    fn test_parking_placement() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "parking:lane:left=half_on_kerb",
+            "parking:lane:right=diagonal",
+        ]));
+        let left = &back[1];
+        assert_eq!(LaneType::Parking, left.lt);
+        assert_eq!(ParkingOrientation::Parallel, left.parking_orientation);
+        assert_eq!(ParkingPlacement::HalfOnKerb, left.parking_placement);
+        assert_eq!(
+            default_lane_width(LaneType::Parking) * 0.5,
+            left.parking_width_on_street()
+        );
+        let right = &fwd[1];
+        assert_eq!(ParkingOrientation::Diagonal, right.parking_orientation);
+        assert_eq!(ParkingPlacement::OnStreet, right.parking_placement);
+        assert_eq!(
+            default_lane_width(LaneType::Parking),
+            right.parking_width_on_street()
+        );
+        // Angled parking fits more cars
+        assert_eq!(3, left.number_parking_spots(Distance::meters(40.0)));
+        assert_eq!(6, right.number_parking_spots(Distance::meters(40.0)));
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "parking:lane:both=parallel",
+            "parking:lane:both:parallel=half_on_kerb",
+        ]));
+        assert_eq!(ParkingPlacement::HalfOnKerb, fwd[1].parking_placement);
+    }
+
+    #[test]
+    fn test_shoulders() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=motorway",
+            "oneway=yes",
+            "lanes=2",
+            "shoulder=yes",
+            "sidewalk=none",
+        ]));
+        assert_eq!(
+            vec![LaneType::Driving, LaneType::Driving, LaneType::Shoulder],
+            fwd.iter().map(|l| l.lt).collect::<Vec<_>>()
+        );
+        assert!(back.is_empty());
+        assert!(!fwd[2].bikes_allowed);
+
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=motorway",
+            "oneway=yes",
+            "lanes=2",
+            "shoulder=yes",
+            "bicycle=yes",
+            "sidewalk=none",
+        ]));
+        assert!(fwd[2].bikes_allowed);
+        // Cyclists still can't use the driving lanes
+        assert!(!fwd[0].bikes_allowed);
+    }
+
+    #[test]
+    fn test_emergency_access() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=service",
+            "access=no",
+            "emergency=yes",
+            "sidewalk=none",
+        ]));
+        assert_lanes("d", &fwd.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert_eq!(Access::Emergency, fwd[0].access);
+        assert_eq!(Access::Emergency, back[0].access);
+
+        // Emergency vehicles being allowed doesn't restrict anybody else
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=service", "emergency=yes"]));
+        assert_eq!(Access::Public, fwd[0].access);
+    }
+
+    #[test]
+    fn test_raceways() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=raceway",
+            "oneway=yes",
+            "lanes=2",
+            "sidewalk=both",
+            "parking:lane:both=parallel",
+        ]));
+        assert_lanes("dd", &fwd.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert!(back.is_empty());
+        assert!(fwd.iter().all(|l| l.access == Access::Private));
+
+        let (fwd, back) = get_lane_details(&tags(vec!["highway=raceway"]));
+        assert_lanes("d", &fwd.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert_lanes("d", &back.iter().map(|l| l.lt).collect::<Vec<_>>());
+        assert_eq!(Access::Private, fwd[0].access);
+    }
+
+    #[test]
+    fn test_reversed_oneway() {
+        // Traffic moves against the way, so the left side of the way is the curb.
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=primary",
+            "oneway=-1",
+            "lanes=2",
+            "cycleway:left=lane",
+            "parking:lane:left=parallel",
+        ]));
+        assert_lanes("", &fwd);
+        assert_lanes("ddbp", &back);
+        // A bike lane on the right goes against traffic.
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "oneway=-1",
+            "cycleway:right=lane",
+            "sidewalk=both",
+        ]));
+        assert_lanes("bs", &fwd);
+        assert_lanes("ds", &back);
+        // Explicit lanes going with the way contradict the oneway tag.
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "oneway=-1",
+            "lanes:forward=1",
+            "lanes:backward=1",
+        ]));
+        assert_lanes("d", &fwd);
+        assert_lanes("d", &back);
+
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "oneway=-1",
+            "turn:lanes=left|through",
+            "lanes=2",
+        ]));
+        assert!(fwd.is_empty());
+        assert_eq!(
+            vec![vec![TurnIndication::Left], vec![TurnIndication::Through]],
+            back.into_iter().map(|l| l.turns).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_hgv_restrictions() {
+        let hgv = |kv: Vec<&str>| {
+            let (fwd, back) = get_lane_details(&tags(kv));
+            (
+                fwd.into_iter().map(|l| l.hgv_allowed).collect::<Vec<_>>(),
+                back.into_iter().map(|l| l.hgv_allowed).collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            (vec![true], vec![true]),
+            hgv(vec!["highway=secondary", "lanes=2"])
+        );
+        assert_eq!(
+            (vec![false], vec![false]),
+            hgv(vec!["highway=secondary", "lanes=2", "hgv=no"])
+        );
+        assert_eq!(
+            (vec![true, false], Vec::new()),
+            hgv(vec![
+                "highway=secondary",
+                "oneway=yes",
+                "lanes=2",
+                "hgv:lanes=yes|no",
+            ])
+        );
+        assert_eq!(
+            (vec![false, true], vec![true, true]),
+            hgv(vec![
+                "highway=secondary",
+                "lanes=4",
+                "hgv:lanes:forward=no|designated",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_goods_restrictions() {
+        let goods = |kv: Vec<&str>| {
+            let (fwd, back) = get_lane_details(&tags(kv));
+            (
+                fwd.into_iter().map(|l| l.goods_allowed).collect::<Vec<_>>(),
+                back.into_iter()
+                    .map(|l| l.goods_allowed)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            (vec![true], vec![true]),
+            goods(vec!["highway=secondary", "lanes=2"])
+        );
+        assert_eq!(
+            (vec![false], vec![false]),
+            goods(vec!["highway=secondary", "lanes=2", "goods=no"])
+        );
+        assert_eq!(
+            (vec![true, false], Vec::new()),
+            goods(vec![
+                "highway=secondary",
+                "oneway=yes",
+                "lanes=2",
+                "goods:lanes=yes|no",
+            ])
+        );
+
+        // Restricting one doesn't restrict the other
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=secondary", "lanes=2", "hgv=no"]));
+        assert!(!fwd[0].hgv_allowed);
+        assert!(fwd[0].goods_allowed);
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=secondary", "lanes=2", "goods=no"]));
+        assert!(fwd[0].hgv_allowed);
+        assert!(!fwd[0].goods_allowed);
+    }
+
+    #[test]
+    fn test_mismatched_lane_flags() {
+        // Missing entries fall back to the road-wide default
+        let mut warnings = Vec::new();
+        let (fwd, _) = lane_details(
+            &tags(vec![
+                "highway=secondary",
+                "oneway=yes",
+                "lanes=3",
+                "hgv=no",
+                "hgv:lanes=yes",
+            ]),
+            &mut warnings,
+        );
+        assert_eq!(
+            vec![true, false, false],
+            fwd.iter().map(|l| l.hgv_allowed).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                "hgv:lanes has 1 entries, but there are 3 lanes; treating the rest as unmarked"
+                    .to_string()
+            ],
+            warnings
+        );
+
+        // Extra entries are dropped
+        let mut warnings = Vec::new();
+        let (fwd, _) = lane_details(
+            &tags(vec![
+                "highway=primary",
+                "oneway=yes",
+                "lanes=2",
+                "bus:lanes=|designated",
+                "bus:lanes:conditional=|designated @ (Mo-Fr 07:00-09:00)|",
+            ]),
+            &mut warnings,
+        );
+        assert_eq!(Some("Mo-Fr 07:00-09:00".to_string()), fwd[1].bus_only_when);
+        assert_eq!(1, warnings.len());
+    }
+
+    #[test]
+    fn test_conditional_bus_lanes() {
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=primary",
+            "oneway=yes",
+            "lanes=2",
+            "bus:lanes=|designated",
+            "bus:lanes:conditional=|designated @ (Mo-Fr 07:00-09:00)",
+        ]));
+        assert_eq!(LaneType::Driving, fwd[0].lt);
+        assert_eq!(None, fwd[0].bus_only_when);
+        assert_eq!(LaneType::Bus, fwd[1].lt);
+        assert_eq!(Some("Mo-Fr 07:00-09:00".to_string()), fwd[1].bus_only_when);
+
+        // Without the conditional tag, the lane is always for buses.
+        let (fwd, _) = get_lane_details(&tags(vec![
+            "highway=primary",
+            "oneway=yes",
+            "lanes=2",
+            "bus:lanes=|designated",
+        ]));
+        assert_eq!(None, fwd[1].bus_only_when);
+    }
+
+    #[test]
+    fn test_traffic_calming() {
+        let (fwd, back) = get_lane_details(&tags(vec![
+            "highway=residential",
+            "traffic_calming=bump",
+            "sidewalk=both",
+        ]));
+        assert_eq!(Some(TrafficCalming::Bump), fwd[0].traffic_calming);
+        assert_eq!(Some(TrafficCalming::Bump), back[0].traffic_calming);
+        assert_eq!(None, fwd[1].traffic_calming);
+        assert_lanes("ds", &fwd.into_iter().map(|l| l.lt).collect::<Vec<_>>());
+
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential", "traffic_calming=yes"]));
+        assert_eq!(Some(TrafficCalming::Other), fwd[0].traffic_calming);
+        let (fwd, _) = get_lane_details(&tags(vec!["highway=residential", "sidewalk=both"]));
+        assert_eq!(None, fwd[0].traffic_calming);
+    }
+
+    #[test]
+    fn test_overtaking() {
+        let overtaking = |kv: Vec<&str>| {
+            let (fwd, back) = get_lane_details(&tags(kv));
+            (
+                fwd.into_iter().map(|l| l.overtaking).collect::<Vec<_>>(),
+                back.into_iter().map(|l| l.overtaking).collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            (vec![true], vec![true]),
+            overtaking(vec!["highway=secondary", "lanes=2"])
+        );
+        assert_eq!(
+            (vec![false], vec![false]),
+            overtaking(vec!["highway=secondary", "lanes=2", "overtaking=no"])
+        );
+        assert_eq!(
+            (vec![true], vec![false]),
+            overtaking(vec!["highway=secondary", "lanes=2", "overtaking=forward"])
+        );
+        assert_eq!(
+            (vec![false], vec![true]),
+            overtaking(vec![
+                "highway=secondary",
+                "lanes=2",
+                "overtaking:forward=no"
+            ])
+        );
+        assert_eq!(
+            (vec![true, false], Vec::new()),
+            overtaking(vec![
+                "highway=secondary",
+                "oneway=yes",
+                "lanes=2",
+                "overtaking:lanes=yes|no",
+            ])
+        );
+    }
+}
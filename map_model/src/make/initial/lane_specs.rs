@@ -1,10 +1,127 @@
-use crate::{osm, LaneType};
+use crate::parse_units::{parse_distance, parse_speed, parse_weight};
+use crate::{osm, LaneType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
+use abstutil::Warn;
+use geom::{Distance, Speed};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::{fmt, iter};
 
 // (original direction, reversed direction)
 pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Vec<LaneType>) {
+    get_lane_types_with_policy(osm_tags, &LaneInferencePolicy::default())
+}
+
+// Like get_lane_types, but with different assumptions about what's missing from the tags.
+pub fn get_lane_types_with_policy(
+    osm_tags: &BTreeMap<String, String>,
+    policy: &LaneInferencePolicy,
+) -> (Vec<LaneType>, Vec<LaneType>) {
+    lane_types(osm_tags, policy, &mut Vec::new())
+}
+
+// Like get_lane_types, but also complains about contradictory or malformed tags.
+pub fn get_lane_types_warn(
+    osm_tags: &BTreeMap<String, String>,
+) -> Warn<(Vec<LaneType>, Vec<LaneType>)> {
+    let mut warnings = Vec::new();
+    let result = lane_types(osm_tags, &LaneInferencePolicy::default(), &mut warnings);
+    Warn::warnings(result, warnings)
+}
+
+// Like get_lane_types_warn, but for many ways at once. Each warning says which way (by index) it's
+// about. This is only a convenience; in benches/lane_inference.rs, it's never faster than calling
+// get_lane_types_warn for each way, and often a little slower.
+pub fn get_lane_types_batch(
+    all_tags: &[BTreeMap<String, String>],
+) -> Warn<Vec<(Vec<LaneType>, Vec<LaneType>)>> {
+    let policy = LaneInferencePolicy::default();
+    let mut results = Vec::with_capacity(all_tags.len());
+    let mut warnings = Vec::new();
+    let mut scratch = Vec::new();
+    for (idx, osm_tags) in all_tags.iter().enumerate() {
+        results.push(lane_types(osm_tags, &policy, &mut scratch));
+        warnings.extend(scratch.drain(..).map(|x| format!("way #{}: {}", idx, x)));
+    }
+    Warn::warnings(results, warnings)
+}
+
+// Every key that get_lane_types looks at, so tools can report what's ignored on a way. One caveat:
+// a way with no keys besides the highway class, name, and ref gets default lanes for its class, so
+// the presence of any other key matters. Keep this in sync with lane_types.
+pub fn consumed_tags() -> &'static [&'static str] {
+    &[
+        osm::SYNTHETIC_LANES,
+        osm::HIGHWAY,
+        "area",
+        "junction",
+        "foot",
+        "bicycle",
+        "segregated",
+        "oneway",
+        "oneway:bicycle",
+        "oneway:foot",
+        "lanes",
+        "lanes:forward",
+        "lanes:backward",
+        "lanes:both_ways",
+        "lane_markings",
+        "access",
+        "bus",
+        "centre_turn_lane",
+        "bus:lanes",
+        "busway",
+        "busway:both",
+        "busway:left",
+        "busway:right",
+        "cycleway",
+        "cycleway:left",
+        "cycleway:right",
+        "cycleway:placement",
+        osm::PARKING_LEFT,
+        osm::PARKING_RIGHT,
+        osm::PARKING_BOTH,
+        "shoulder",
+        "shoulder:left",
+        "shoulder:right",
+        "shoulder:both",
+        osm::SIDEWALK,
+        "sidewalk:left",
+        "sidewalk:right",
+        "sidewalk:both",
+        "vehicle",
+        "motor_vehicle",
+        "motorroad",
+    ]
+}
+
+// What to assume when the tags don't say.
+#[derive(Clone, Debug)]
+pub struct LaneInferencePolicy {
+    // When the lane count isn't tagged, how many driving lanes go each way?
+    pub default_lanes_per_direction: usize,
+    // For roads with nothing tagged besides the class, put sidewalks on both sides of everything
+    // besides motorways, trunks, and service roads. This is common in the US.
+    pub untagged_sidewalks: bool,
+    // For residential roads with nothing tagged besides the class, put parking on both sides.
+    // Parking is never assumed anywhere else, especially not motorways.
+    pub untagged_residential_parking: bool,
+}
+
+impl Default for LaneInferencePolicy {
+    fn default() -> LaneInferencePolicy {
+        LaneInferencePolicy {
+            default_lanes_per_direction: 1,
+            untagged_sidewalks: true,
+            untagged_residential_parking: true,
+        }
+    }
+}
+
+pub(crate) fn lane_types(
+    osm_tags: &BTreeMap<String, String>,
+    policy: &LaneInferencePolicy,
+    warnings: &mut Vec<String>,
+) -> (Vec<LaneType>, Vec<LaneType>) {
     if let Some(s) = osm_tags.get(osm::SYNTHETIC_LANES) {
         if let Some(spec) = RoadSpec::parse(s.to_string()) {
             return (spec.fwd, spec.back);
@@ -13,72 +130,187 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
         }
     }
 
+    let parsed = ParsedTags::new(osm_tags);
+
     // Easy special cases first.
-    if osm_tags.get("junction") == Some(&"roundabout".to_string()) {
+    if is_area(osm_tags) {
+        return (Vec::new(), Vec::new());
+    }
+    if CircularJunction::parse(osm_tags).is_some() {
         return (vec![LaneType::Driving, LaneType::Sidewalk], Vec::new());
     }
-    if osm_tags.get(osm::HIGHWAY) == Some(&"footway".to_string()) {
+    // Indoor corridors are only walkable too; get_lane_details marks them.
+    if matches!(parsed.highway, Some("footway") | Some("corridor")) {
+        // For people only walking against the way, put the sidewalk on the back side. Either way,
+        // get_lane_details marks it as oneway.
+        if osm::has_value(osm_tags, "oneway:foot", "-1") {
+            return (Vec::new(), vec![LaneType::Sidewalk]);
+        }
+        return (vec![LaneType::Sidewalk], Vec::new());
+    }
+    // Bike paths and crossings are only for bikes. get_lane_details marks the crossing.
+    if is_bike_crossing(osm_tags) || parsed.highway == Some("cycleway") {
+        let (fwd, back) = bike_directions(osm_tags);
+        let lane = |allowed: bool| {
+            if allowed {
+                vec![LaneType::Biking]
+            } else {
+                Vec::new()
+            }
+        };
+        return (lane(fwd), lane(back));
+    }
+    if let Some(segregated) = shared_use_path(osm_tags) {
+        if segregated {
+            let (fwd, back) = bike_directions(osm_tags);
+            let mut fwd_side = vec![LaneType::Sidewalk];
+            if fwd {
+                fwd_side.insert(0, LaneType::Biking);
+            }
+            let back_side = if back {
+                vec![LaneType::Biking]
+            } else {
+                Vec::new()
+            };
+            return (fwd_side, back_side);
+        }
+        // Pedestrians and cyclists share one lane. get_lane_details marks it as allowing bikes.
         return (vec![LaneType::Sidewalk], Vec::new());
     }
+    // Dedicated bus corridors, with no general traffic. (bus_guideway isn't imported at all.)
+    if parsed.highway == Some("busway") {
+        if parsed.reversed {
+            return (Vec::new(), vec![LaneType::Bus]);
+        }
+        if parsed.oneway {
+            return (vec![LaneType::Bus], Vec::new());
+        }
+        return (vec![LaneType::Bus], vec![LaneType::Bus]);
+    }
 
-    // TODO Reversible roads should be handled differently?
-    let oneway = osm_tags.get("oneway") == Some(&"yes".to_string())
-        || osm_tags.get("oneway") == Some(&"reversible".to_string());
+    if let Some(class) = parsed.class {
+        if is_untagged(osm_tags) {
+            let spec = synthesize_defaults(class, parsed.oneway, policy);
+            return (spec.fwd, spec.back);
+        }
+    }
 
-    // How many driving lanes in each direction?
-    let num_driving_fwd = if let Some(n) = osm_tags
-        .get("lanes:forward")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
-        n
-    } else if let Some(n) = osm_tags
-        .get("lanes")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
-        if oneway {
-            n
-        } else if n % 2 == 0 {
-            n / 2
+    let oneway = parsed.oneway;
+    // Only lanes in the contraflow direction can make a tagged oneway two-way.
+    if !oneway {
+        let contraflow_key = if osm::has_value(osm_tags, "oneway", osm::YES) {
+            Some("lanes:backward")
+        } else if osm::has_value(osm_tags, "oneway", "-1") {
+            Some("lanes:forward")
         } else {
-            // TODO Really, this is ambiguous, but...
-            (n / 2).max(1)
+            None
+        };
+        if let Some(key) = contraflow_key {
+            warnings.push(format!(
+                "oneway={}, but {}={}; treating the road as two-way",
+                osm_tags["oneway"], key, osm_tags[key]
+            ));
         }
+    }
+
+    // Build everything from the perspective of traffic, even for oneway=-1, and flip the sides at
+    // the end.
+    let reversed = parsed.reversed;
+    let right = Side::curb_of(true, reversed);
+    let left = Side::curb_of(false, reversed);
+    let (fwd_key, back_key) = if reversed {
+        ("lanes:backward", "lanes:forward")
     } else {
-        // TODO Grrr.
-        1
+        ("lanes:forward", "lanes:backward")
     };
-    let num_driving_back = if let Some(n) = osm_tags
-        .get("lanes:backward")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
+
+    // How many driving lanes in each direction?
+    let mut parse_count = |key: &str| {
+        let value = osm_tags.get(key)?;
+        if let Ok(n) = value.parse::<usize>() {
+            return Some(n);
+        }
+        let n = parse_lane_count(value);
+        if let Some(n) = n {
+            warnings.push(format!(
+                "{}={} isn't a whole number, using {}",
+                key, value, n
+            ));
+        } else {
+            warnings.push(format!("can't parse {}={}", key, value));
+        }
         n
-    } else if let Some(n) = osm_tags
-        .get("lanes")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
-        if oneway {
-            0
-        } else if n % 2 == 0 {
+    };
+    let half = |n: usize| {
+        if n % 2 == 0 {
             n / 2
         } else {
             // TODO Really, this is ambiguous, but...
             (n / 2).max(1)
         }
-    } else {
-        // TODO Grrr.
-        if oneway {
-            0
+    };
+    let total = parse_count("lanes");
+    // The center lane counts towards the total
+    let num_center = parse_count("lanes:both_ways").unwrap_or(0);
+    // Whatever's left of the total after the center lane gets split between the two directions.
+    // On a reversible way, lanes:both_ways counts every lane, so there's nothing to subtract.
+    let reversible = osm::has_value(osm_tags, "oneway", "reversible");
+    let split = |n: usize| {
+        if reversible {
+            half(n)
         } else {
-            1
+            half(n.saturating_sub(num_center))
         }
     };
+    let (num_driving_fwd, num_driving_back) =
+        match (total, parse_count(fwd_key), parse_count(back_key)) {
+            (Some(n), Some(fwd), Some(back)) => {
+                if fwd + back + num_center != n {
+                    warnings.push(format!(
+                        "lanes={} doesn't match lanes:forward={} and lanes:backward={}, trusting \
+                         the directional counts",
+                        n, fwd, back
+                    ));
+                }
+                (fwd, back)
+            }
+            // If only one direction is specified, the other is whatever's left over.
+            (Some(n), Some(fwd), None) if !oneway => {
+                (fwd, remaining_lanes(n, fwd + num_center, warnings))
+            }
+            (Some(n), None, Some(back)) if !oneway => {
+                (remaining_lanes(n, back + num_center, warnings), back)
+            }
+            (total, fwd, back) => (
+                fwd.unwrap_or_else(|| match total {
+                    Some(n) if oneway => n,
+                    Some(n) => split(n),
+                    // TODO Grrr.
+                    None => policy.default_lanes_per_direction,
+                }),
+                back.unwrap_or_else(|| match total {
+                    Some(_) | None if oneway => 0,
+                    Some(n) => split(n),
+                    // TODO Grrr.
+                    None => policy.default_lanes_per_direction,
+                }),
+            ),
+        };
+    // https://wiki.openstreetmap.org/wiki/Key:lane_markings. Without markings, vehicles just share
+    // the road, and any lane count is only a guess at how many fit side by side. Model one (wide)
+    // lane each way instead.
+    let (num_driving_fwd, num_driving_back) = if is_unmarked(osm_tags) {
+        (1, if oneway { 0 } else { 1 })
+    } else {
+        (num_driving_fwd, num_driving_back)
+    };
 
     // Sup West Seattle
-    let driving_lane = if osm_tags.get("access") == Some(&"no".to_string())
-        && osm_tags.get("bus") == Some(&"yes".to_string())
+    let driving_lane = if osm::has_value(osm_tags, "access", osm::NO)
+        && osm::has_value(osm_tags, "bus", osm::YES)
     {
         LaneType::Bus
-    } else if osm_tags.get("highway") == Some(&"construction".to_string()) {
+    } else if parsed.highway == Some("construction") {
         LaneType::Construction
     } else {
         LaneType::Driving
@@ -87,74 +319,765 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
     let mut fwd_side: Vec<LaneType> = iter::repeat(driving_lane).take(num_driving_fwd).collect();
     let mut back_side: Vec<LaneType> = iter::repeat(driving_lane).take(num_driving_back).collect();
     // TODO Fix upstream. https://wiki.openstreetmap.org/wiki/Key:centre_turn_lane
-    if osm_tags.get("lanes:both_ways") == Some(&"1".to_string())
-        || osm_tags.get("centre_turn_lane") == Some(&"yes".to_string())
-    {
+    // The center lane is usually for turning, even without turn:lanes:both_ways. On a reversible
+    // way, lanes:both_ways just means every lane switches direction, and they're already counted.
+    if !reversible && (num_center > 0 || osm::has_value(osm_tags, "centre_turn_lane", osm::YES)) {
         fwd_side.insert(0, LaneType::SharedLeftTurn);
     }
 
-    if driving_lane == LaneType::Construction {
+    // Race tracks are just for driving, no matter what else is tagged. get_lane_details restricts
+    // access.
+    if driving_lane == LaneType::Construction || parsed.highway == Some("raceway") {
+        if reversed {
+            return (back_side, fwd_side);
+        }
         return (fwd_side, back_side);
     }
 
     // TODO Handle bus lanes properly.
     let has_bus_lane = osm_tags.contains_key("bus:lanes");
-    if has_bus_lane {
-        fwd_side.pop();
-        fwd_side.push(LaneType::Bus);
-        if !back_side.is_empty() {
-            back_side.pop();
-            back_side.push(LaneType::Bus);
-        }
+    // https://wiki.openstreetmap.org/wiki/Key:busway
+    let busway = |key: &str| osm::has_value(osm_tags, key, "lane");
+    let busway_fwd =
+        busway("busway") || busway("busway:both") || busway(&format!("busway:{}", right.as_str()));
+    let busway_back =
+        busway("busway") || busway("busway:both") || busway(&format!("busway:{}", left.as_str()));
+    if has_bus_lane || busway_fwd {
+        add_bus_lane(&mut fwd_side);
+    }
+    if has_bus_lane || busway_back {
+        add_bus_lane(&mut back_side);
     }
 
-    if osm_tags.get("cycleway") == Some(&"lane".to_string()) {
+    // A two-way cycle track running down the middle of the road, instead of along the curbs. Each
+    // direction of bikes gets a lane next to the centerline (or the turn lane).
+    let center_bikes = matches!(parsed.cycleway, Some("track") | Some("lane"))
+        && osm::has_value(osm_tags, "cycleway:placement", "middle");
+    if center_bikes {
+        let idx = if fwd_side.first() == Some(&LaneType::SharedLeftTurn) {
+            1
+        } else {
+            0
+        };
+        fwd_side.insert(idx, LaneType::Biking);
+        back_side.insert(0, LaneType::Biking);
+    } else if parsed.cycleway == Some("lane") {
         fwd_side.push(LaneType::Biking);
         if !back_side.is_empty() {
             back_side.push(LaneType::Biking);
         }
     } else {
-        if osm_tags.get("cycleway:right") == Some(&"lane".to_string()) {
-            fwd_side.push(LaneType::Biking);
-        }
-        if osm_tags.get("cycleway:left") == Some(&"lane".to_string()) {
-            back_side.push(LaneType::Biking);
+        // cycleway=no vetoes bike lanes, unless a specific side contradicts it. The more specific
+        // tag is probably the more recent edit.
+        let vetoed = parsed.cycleway == Some("no");
+        let mut sided = |side: Side, lanes: &mut Vec<LaneType>| {
+            let key = format!("cycleway:{}", side.as_str());
+            if osm::has_value(osm_tags, &key, "lane") {
+                if vetoed {
+                    warnings.push(format!("cycleway=no, but {}=lane; trusting the side", key));
+                }
+                lanes.push(LaneType::Biking);
+            }
+        };
+        sided(right, &mut fwd_side);
+        sided(left, &mut back_side);
+    }
+    if uses_sidepath(osm_tags) {
+        let before = fwd_side.len() + back_side.len();
+        fwd_side.retain(|lt| *lt != LaneType::Biking);
+        back_side.retain(|lt| *lt != LaneType::Biking);
+        if fwd_side.len() + back_side.len() != before {
+            warnings.push("bicycle=use_sidepath, so ignoring bike lanes".to_string());
         }
     }
 
     if driving_lane == LaneType::Driving {
-        fn has_parking(value: Option<&String>) -> bool {
-            value == Some(&"parallel".to_string())
-                || value == Some(&"diagonal".to_string())
-                || value == Some(&"perpendicular".to_string())
-        }
-        let parking_lane_fwd = has_parking(osm_tags.get(osm::PARKING_RIGHT))
-            || has_parking(osm_tags.get(osm::PARKING_BOTH));
-        let parking_lane_back = has_parking(osm_tags.get(osm::PARKING_LEFT))
-            || has_parking(osm_tags.get(osm::PARKING_BOTH));
-        if parking_lane_fwd {
+        if parsed.parking(right) {
             fwd_side.push(LaneType::Parking);
         }
-        if parking_lane_back {
+        if parsed.parking(left) {
             back_side.push(LaneType::Parking);
         }
     }
 
+    // https://wiki.openstreetmap.org/wiki/Key:shoulder. Either like shoulder=right, or with sided
+    // keys like shoulder:right=yes, which take precedence for their side.
+    let sided_shoulder = |side: Side| {
+        osm_tags
+            .get(&format!("shoulder:{}", side.as_str()))
+            .or_else(|| osm_tags.get("shoulder:both"))
+            .map(|x| x == "yes")
+    };
+    let shoulder = osm_tags.get("shoulder").map(|x| x.as_str());
+    let shoulder_fwd = sided_shoulder(right).unwrap_or_else(|| match shoulder {
+        Some("yes") | Some("both") => true,
+        Some(x) => x == right.as_str(),
+        None => false,
+    });
+    let shoulder_back = sided_shoulder(left).unwrap_or_else(|| match shoulder {
+        // Don't add a shoulder to the empty side of a oneway, unless it's asked for by name
+        Some("yes") | Some("both") => !back_side.is_empty(),
+        Some(x) => x == left.as_str(),
+        None => false,
+    });
+    if shoulder_fwd {
+        fwd_side.push(LaneType::Shoulder);
+    }
+    if shoulder_back {
+        back_side.push(LaneType::Shoulder);
+    }
+
+    // https://wiki.openstreetmap.org/wiki/Key:sidewalk names physical sides of the way, either in
+    // the value or in the key, like sidewalk:left=yes. The key is more specific.
     // TODO Need to snap separate sidewalks to ways. Until then, just do this.
-    if osm_tags.get(osm::SIDEWALK) == Some(&"both".to_string())
-        || osm_tags.get(osm::SIDEWALK) == Some(&"separate".to_string())
-    {
-        fwd_side.push(LaneType::Sidewalk);
-        back_side.push(LaneType::Sidewalk);
-    } else if osm_tags.get(osm::SIDEWALK) == Some(&"right".to_string()) {
+    let has_sidewalk = |side: Side| {
+        if let Some(value) = osm_tags
+            .get(&format!("sidewalk:{}", side.as_str()))
+            .or_else(|| osm_tags.get("sidewalk:both"))
+        {
+            return value == "yes" || value == "separate";
+        }
+        match osm_tags.get(osm::SIDEWALK).map(|x| x.as_str()) {
+            Some("both") | Some("separate") => true,
+            Some(x) => x == side.as_str(),
+            None => false,
+        }
+    };
+    if has_sidewalk(right) {
         fwd_side.push(LaneType::Sidewalk);
-    } else if osm_tags.get(osm::SIDEWALK) == Some(&"left".to_string()) {
+    }
+    if has_sidewalk(left) {
         back_side.push(LaneType::Sidewalk);
     }
 
+    // https://wiki.openstreetmap.org/wiki/Key:access. vehicle=no bans cyclists too, so every lane
+    // for any kind of vehicle goes away, but motor_vehicle=no lets cyclists ride where cars
+    // would've been.
+    if osm::has_value(osm_tags, "vehicle", osm::NO) {
+        let for_vehicles = |lt: &LaneType| {
+            matches!(
+                lt,
+                LaneType::Driving
+                    | LaneType::SharedLeftTurn
+                    | LaneType::Biking
+                    | LaneType::Parking
+                    | LaneType::Bus
+            )
+        };
+        fwd_side.retain(|lt| !for_vehicles(lt));
+        back_side.retain(|lt| !for_vehicles(lt));
+        if fwd_side.is_empty() && back_side.is_empty() {
+            // People can still walk here, so it's effectively a footway. A road needs at least
+            // one lane.
+            fwd_side.push(LaneType::Sidewalk);
+        }
+    } else if osm::has_value(osm_tags, "motor_vehicle", osm::NO) {
+        remove_motor_vehicle_lanes(&mut fwd_side);
+        remove_motor_vehicle_lanes(&mut back_side);
+    }
+
+    // https://wiki.openstreetmap.org/wiki/Key:motorroad. Like a motorway, pedestrians, cyclists,
+    // and parking aren't allowed, even if the tags say otherwise.
+    if osm::has_value(osm_tags, "motorroad", osm::YES) {
+        let banned = |lt: &LaneType| {
+            matches!(
+                lt,
+                LaneType::Sidewalk | LaneType::Biking | LaneType::Parking
+            )
+        };
+        if fwd_side.iter().chain(back_side.iter()).any(banned) {
+            warnings
+                .push("motorroad=yes, so ignoring sidewalks, bike lanes, and parking".to_string());
+            fwd_side.retain(|lt| !banned(lt));
+            back_side.retain(|lt| !banned(lt));
+        }
+    }
+
+    // Slip roads onto and off of motorways and trunk roads. Nobody walks or parks along them, even
+    // if the tags say otherwise. Jughandles are usually at-grade and may have sidewalks, but
+    // there's no room to park.
+    let banned_along_link = |lt: &LaneType| {
+        if is_high_speed_link(osm_tags) {
+            matches!(lt, LaneType::Sidewalk | LaneType::Parking)
+        } else if is_jughandle(osm_tags) {
+            *lt == LaneType::Parking
+        } else {
+            false
+        }
+    };
+    if fwd_side
+        .iter()
+        .chain(back_side.iter())
+        .any(banned_along_link)
+    {
+        warnings.push(format!(
+            "{}={} is a slip road, so ignoring {}",
+            osm::HIGHWAY,
+            parsed.highway.unwrap_or("?"),
+            if is_high_speed_link(osm_tags) {
+                "sidewalks and parking"
+            } else {
+                "parking"
+            }
+        ));
+        fwd_side.retain(|lt| !banned_along_link(lt));
+        back_side.retain(|lt| !banned_along_link(lt));
+    }
+
+    if reversed {
+        return (back_side, fwd_side);
+    }
     (fwd_side, back_side)
 }
 
+fn is_high_speed_link(osm_tags: &BTreeMap<String, String>) -> bool {
+    matches!(
+        osm_tags.get(osm::HIGHWAY).map(|x| x.as_str()),
+        Some("motorway_link") | Some("trunk_link")
+    )
+}
+
+// https://wiki.openstreetmap.org/wiki/Tag:junction=jughandle. A ramp looping around to make a left
+// turn from the right side of the road.
+fn is_jughandle(osm_tags: &BTreeMap<String, String>) -> bool {
+    osm::has_value(osm_tags, "junction", "jughandle")
+}
+
+// A physical side of the way, relative to the direction it's drawn in. OSM tags like
+// cycleway:left refer to this.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    // Which side of the way is the curb for traffic in the fwd direction? When reversed (building
+    // lanes for oneway=-1 as if the way pointed the other way), the sides flip.
+    pub fn curb_of(fwd: bool, reversed: bool) -> Side {
+        if fwd != reversed {
+            Side::Right
+        } else {
+            Side::Left
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+        }
+    }
+}
+
+// The keys lane_types branches on the most, looked up once. Everything else is read straight from
+// the tags. This is mostly for readability. When it was added, benches/lane_inference.rs showed it
+// cutting allocations from 33 to about 27 per way, but no difference in time beyond noise between
+// runs.
+struct ParsedTags<'a> {
+    highway: Option<&'a str>,
+    class: Option<RoadClass>,
+    oneway: bool,
+    reversed: bool,
+    cycleway: Option<&'a str>,
+    // Physical sides of the way
+    parking_left: bool,
+    parking_right: bool,
+}
+
+impl<'a> ParsedTags<'a> {
+    fn new(osm_tags: &'a BTreeMap<String, String>) -> ParsedTags<'a> {
+        let highway = osm_tags.get(osm::HIGHWAY).map(|x| x.as_str());
+        let parking_both = has_parking(osm_tags.get(osm::PARKING_BOTH));
+        ParsedTags {
+            highway,
+            class: highway.and_then(RoadClass::from_highway),
+            oneway: is_oneway(osm_tags),
+            reversed: is_reversed_oneway(osm_tags),
+            cycleway: osm_tags.get("cycleway").map(|x| x.as_str()),
+            parking_left: parking_both || has_parking(osm_tags.get(osm::PARKING_LEFT)),
+            parking_right: parking_both || has_parking(osm_tags.get(osm::PARKING_RIGHT)),
+        }
+    }
+
+    fn parking(&self, side: Side) -> bool {
+        match side {
+            Side::Left => self.parking_left,
+            Side::Right => self.parking_right,
+        }
+    }
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:parking:lane. Besides an orientation, some data just says
+// the parking is marked, or names where it is relative to the kerb.
+pub(crate) fn has_parking(value: Option<&String>) -> bool {
+    matches!(
+        value.map(|x| x.as_str()),
+        Some("parallel")
+            | Some("diagonal")
+            | Some("perpendicular")
+            | Some("marked")
+            | Some("on_kerb")
+            | Some("half_on_kerb")
+            | Some("shoulder")
+    )
+}
+
+// Cyclists get one lane where the driving lanes used to be, if they don't already have one.
+fn remove_motor_vehicle_lanes(side: &mut Vec<LaneType>) {
+    let had_driving = side.contains(&LaneType::Driving);
+    side.retain(|lt| {
+        !matches!(
+            lt,
+            LaneType::Driving | LaneType::SharedLeftTurn | LaneType::Parking
+        )
+    });
+    if had_driving && !side.contains(&LaneType::Biking) {
+        side.insert(0, LaneType::Biking);
+    }
+}
+
+// Turn the outermost driving lane into a bus lane, unless there already is one.
+fn add_bus_lane(side: &mut [LaneType]) {
+    if side.contains(&LaneType::Bus) {
+        return;
+    }
+    if let Some(idx) = side.iter().rposition(|lt| *lt == LaneType::Driving) {
+        side[idx] = LaneType::Bus;
+    }
+}
+
+// Plazas and other areas are polygons, not linear ways, so they don't have a cross-section of lanes.
+// convert_osm shouldn't import these as roads at all.
+pub fn is_area(osm_tags: &BTreeMap<String, String>) -> bool {
+    osm::has_value(osm_tags, "area", osm::YES)
+}
+
+// Physical limits on vehicles using the whole road, usually from bridges and tunnels. These don't
+// change the lanes, but freight routing should avoid roads that its vehicles don't fit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeLimits {
+    pub max_height: Option<Distance>,
+    pub max_width: Option<Distance>,
+    // In metric tonnes
+    pub max_weight: Option<f64>,
+}
+
+impl SizeLimits {
+    // https://wiki.openstreetmap.org/wiki/Key:maxheight and friends. Values like "default" or
+    // "none" that don't name a limit are ignored.
+    pub fn parse(osm_tags: &BTreeMap<String, String>) -> SizeLimits {
+        SizeLimits {
+            max_height: osm_tags.get("maxheight").and_then(|x| parse_distance(x)),
+            max_width: osm_tags.get("maxwidth").and_then(|x| parse_distance(x)),
+            max_weight: osm_tags.get("maxweight").and_then(|x| parse_weight(x)),
+        }
+    }
+}
+
+// The tagged width of the road. width is the whole right-of-way, including sidewalks and parking,
+// and width:carriageway is only the part vehicles drive on. See
+// https://wiki.openstreetmap.org/wiki/Key:width.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoadWidths {
+    pub total: Option<Distance>,
+    pub carriageway: Option<Distance>,
+}
+
+impl RoadWidths {
+    pub fn parse(osm_tags: &BTreeMap<String, String>) -> RoadWidths {
+        RoadWidths {
+            total: osm_tags.get("width").and_then(|x| parse_distance(x)),
+            carriageway: osm_tags
+                .get("width:carriageway")
+                .and_then(|x| parse_distance(x)),
+        }
+    }
+}
+
+// How people refer to the road, for labels and search. Like the lanes, this comes from the way's
+// tags, but doesn't affect them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoadLabels {
+    pub name: Option<String>,
+    // https://wiki.openstreetmap.org/wiki/Key:ref, like "I 5" or "SR 520"
+    pub route_ref: Option<String>,
+}
+
+impl RoadLabels {
+    pub fn parse(osm_tags: &BTreeMap<String, String>) -> RoadLabels {
+        let get = |key: &str| osm_tags.get(key).filter(|x| !x.is_empty()).cloned();
+        RoadLabels {
+            name: get(osm::NAME),
+            route_ref: get("ref"),
+        }
+    }
+}
+
+// What the road is built on or through. These don't change the lanes, but matter for drawing and
+// some routing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Structure {
+    pub bridge: bool,
+    pub tunnel: bool,
+    // Raised above the surrounding ground
+    pub embankment: bool,
+    // Dug below the surrounding ground, but still open to the sky
+    pub cutting: bool,
+    // https://wiki.openstreetmap.org/wiki/Key:layer. Relative vertical ordering; 0 is the ground.
+    pub layer: isize,
+}
+
+impl Structure {
+    pub fn parse(osm_tags: &BTreeMap<String, String>) -> Structure {
+        // Values like bridge=viaduct and tunnel=building_passage describe the kind of structure
+        let is = |key: &str| osm_tags.get(key).map(|x| x != "no").unwrap_or(false);
+        Structure {
+            bridge: is("bridge"),
+            tunnel: is("tunnel"),
+            embankment: is("embankment"),
+            cutting: is("cutting"),
+            layer: osm_tags
+                .get("layer")
+                .and_then(|x| x.parse::<isize>().ok())
+                .unwrap_or(0),
+        }
+    }
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:noexit. The way leads nowhere, so routing and searching
+// for parking shouldn't bother exploring it. Lanes are unaffected.
+pub fn is_dead_end(osm_tags: &BTreeMap<String, String>) -> bool {
+    osm::has_value(osm_tags, "noexit", osm::YES)
+}
+
+// https://wiki.openstreetmap.org/wiki/Tag:bicycle=use_sidepath. Cyclists must use a separate path
+// running alongside, so the road itself never gets bike lanes.
+pub fn uses_sidepath(osm_tags: &BTreeMap<String, String>) -> bool {
+    osm::has_value(osm_tags, "bicycle", "use_sidepath")
+}
+
+// https://wiki.openstreetmap.org/wiki/Tag:cycleway=crossing. A short cycleway (or path) where
+// cyclists cross a road.
+pub fn is_bike_crossing(osm_tags: &BTreeMap<String, String>) -> bool {
+    matches!(
+        osm_tags.get(osm::HIGHWAY).map(|x| x.as_str()),
+        Some("cycleway") | Some("path")
+    ) && osm::has_value(osm_tags, "cycleway", "crossing")
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:maxspeed:advisory. A recommended speed, usually posted
+// before curves and on ramps. Unlike maxspeed, nobody enforces it.
+pub fn advisory_speed_limit(osm_tags: &BTreeMap<String, String>) -> Option<Speed> {
+    osm_tags
+        .get("maxspeed:advisory")
+        .and_then(|x| parse_speed(x))
+}
+
+// Both kinds of junctions are implicitly oneway.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CircularJunction {
+    // Entering traffic yields to traffic already circulating
+    Roundabout,
+    // https://wiki.openstreetmap.org/wiki/Tag:junction=circular. No such priority rule.
+    Circular,
+}
+
+impl CircularJunction {
+    pub fn parse(osm_tags: &BTreeMap<String, String>) -> Option<CircularJunction> {
+        match osm_tags.get("junction").map(|x| x.as_str()) {
+            Some("roundabout") => Some(CircularJunction::Roundabout),
+            Some("circular") => Some(CircularJunction::Circular),
+            _ => None,
+        }
+    }
+}
+
+// For paths designated for both pedestrians and cyclists, are they segregated? None if this isn't
+// such a path. https://wiki.openstreetmap.org/wiki/Key:segregated
+pub(crate) fn shared_use_path(osm_tags: &BTreeMap<String, String>) -> Option<bool> {
+    if osm::has_value(osm_tags, osm::HIGHWAY, "path")
+        && osm::has_value(osm_tags, "foot", "designated")
+        && osm::has_value(osm_tags, "bicycle", "designated")
+    {
+        Some(osm::has_value(osm_tags, "segregated", osm::YES))
+    } else {
+        None
+    }
+}
+
+// The kind of road, according to https://wiki.openstreetmap.org/wiki/Key:highway. Links count as
+// whatever they link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoadClass {
+    Motorway,
+    Trunk,
+    Primary,
+    Secondary,
+    Tertiary,
+    Residential,
+    Service,
+}
+
+impl RoadClass {
+    pub fn from_highway(value: &str) -> Option<RoadClass> {
+        match value.trim_end_matches("_link") {
+            "motorway" => Some(RoadClass::Motorway),
+            "trunk" => Some(RoadClass::Trunk),
+            "primary" => Some(RoadClass::Primary),
+            "secondary" => Some(RoadClass::Secondary),
+            "tertiary" => Some(RoadClass::Tertiary),
+            "residential" | "unclassified" => Some(RoadClass::Residential),
+            "service" => Some(RoadClass::Service),
+            _ => None,
+        }
+    }
+}
+
+// A complete cross-section for a road of some class that's missing all lane tagging. Oneway roads,
+// like the implicitly oneway motorways, have nothing on the back side.
+pub fn synthesize_defaults(
+    class: RoadClass,
+    oneway: bool,
+    policy: &LaneInferencePolicy,
+) -> RoadSpec {
+    let num_driving = match class {
+        RoadClass::Motorway | RoadClass::Trunk | RoadClass::Primary => 2,
+        _ => 1,
+    };
+    let mut side = vec![LaneType::Driving; num_driving];
+    if policy.untagged_residential_parking && class == RoadClass::Residential {
+        side.push(LaneType::Parking);
+    }
+    if policy.untagged_sidewalks
+        && !matches!(
+            class,
+            RoadClass::Motorway | RoadClass::Trunk | RoadClass::Service
+        )
+    {
+        side.push(LaneType::Sidewalk);
+    }
+    if oneway {
+        return RoadSpec {
+            fwd: side,
+            back: Vec::new(),
+        };
+    }
+    RoadSpec {
+        fwd: side.clone(),
+        back: side,
+    }
+}
+
+pub(crate) fn is_unmarked(osm_tags: &BTreeMap<String, String>) -> bool {
+    osm::has_value(osm_tags, "lane_markings", osm::NO)
+}
+
+// Is there nothing besides the highway class to go off of? Names and A/B Street's own keys don't
+// count. Note convert_osm always fills out sidewalk and parking tags, so this mostly matters for
+// maps produced some other way.
+fn is_untagged(osm_tags: &BTreeMap<String, String>) -> bool {
+    osm_tags
+        .keys()
+        .all(|k| k == osm::HIGHWAY || k == osm::NAME || k == "ref" || k.starts_with("abst:"))
+}
+
+// Be tolerant of some malformed counts: round decimals, and take the largest number from lists like
+// "2;3".
+fn parse_lane_count(value: &str) -> Option<usize> {
+    if let Ok(n) = value.parse::<usize>() {
+        return Some(n);
+    }
+    value
+        .split(';')
+        .filter_map(|x| x.trim().parse::<f64>().ok())
+        .filter(|x| *x >= 0.0)
+        .map(|x| x.round() as usize)
+        .max()
+}
+
+fn remaining_lanes(total: usize, used: usize, warnings: &mut Vec<String>) -> usize {
+    if used > total {
+        warnings.push(format!(
+            "lanes={} is less than the {} lanes in one direction",
+            total, used
+        ));
+        0
+    } else {
+        total - used
+    }
+}
+
+pub(crate) fn is_oneway(osm_tags: &BTreeMap<String, String>) -> bool {
+    // TODO Reversible roads should be handled differently?
+    let tagged_oneway = match osm_tags.get("oneway").map(|x| x.as_str()) {
+        Some("yes") | Some("reversible") | Some("-1") => true,
+        // Motorways, their links, and jughandles are implicitly oneway
+        None => {
+            osm::has_value(osm_tags, osm::HIGHWAY, "motorway")
+                || osm::has_value(osm_tags, osm::HIGHWAY, "motorway_link")
+                || is_jughandle(osm_tags)
+        }
+        _ => false,
+    };
+    // Contradictory, but explicit lanes going the other way are more specific than the oneway tag,
+    // so trust them.
+    tagged_oneway && !has_contraflow_lanes(osm_tags)
+}
+
+// https://wiki.openstreetmap.org/wiki/Key:oneway:bicycle. May cyclists go (fwd, back)? Without the
+// specific tag, they follow oneway.
+fn bike_directions(osm_tags: &BTreeMap<String, String>) -> (bool, bool) {
+    match osm_tags.get("oneway:bicycle").map(|x| x.as_str()) {
+        Some("yes") => (true, false),
+        Some("-1") => (false, true),
+        Some("no") => (true, true),
+        _ => {
+            if is_reversed_oneway(osm_tags) {
+                (false, true)
+            } else if is_oneway(osm_tags) {
+                (true, false)
+            } else {
+                (true, true)
+            }
+        }
+    }
+}
+
+// oneway=-1 means traffic only moves against the direction the way is drawn.
+pub(crate) fn is_reversed_oneway(osm_tags: &BTreeMap<String, String>) -> bool {
+    osm::has_value(osm_tags, "oneway", "-1") && is_oneway(osm_tags)
+}
+
+fn has_contraflow_lanes(osm_tags: &BTreeMap<String, String>) -> bool {
+    let key = if osm::has_value(osm_tags, "oneway", "-1") {
+        "lanes:forward"
+    } else {
+        "lanes:backward"
+    };
+    osm_tags
+        .get(key)
+        .and_then(|num| parse_lane_count(num))
+        .map(|n| n > 0)
+        .unwrap_or(false)
+}
+
+// How a hand-edited spec differs from what the OSM tags imply. The right side is fwd.
+#[derive(Debug, PartialEq)]
+pub enum Discrepancy {
+    ExtraLanes {
+        side: Side,
+        lt: LaneType,
+        count: usize,
+    },
+    MissingLanes {
+        side: Side,
+        lt: LaneType,
+        count: usize,
+    },
+    // The same lanes are present, just in a different order.
+    DifferentOrder {
+        side: Side,
+    },
+}
+
+// For QA on roads edited in A/B Street, compare the spec against whatever the tags (besides the
+// override) would produce.
+pub fn compare_to_osm(spec: &RoadSpec, osm_tags: &BTreeMap<String, String>) -> Vec<Discrepancy> {
+    let mut tags = osm_tags.clone();
+    tags.remove(osm::SYNTHETIC_LANES);
+    let (fwd, back) = get_lane_types(&tags);
+
+    let mut results = Vec::new();
+    compare_side(Side::Right, &spec.fwd, &fwd, &mut results);
+    compare_side(Side::Left, &spec.back, &back, &mut results);
+    results
+}
+
+fn compare_side(
+    side: Side,
+    actual: &[LaneType],
+    expected: &[LaneType],
+    results: &mut Vec<Discrepancy>,
+) {
+    let mut same_counts = true;
+    for lt in LaneType::all() {
+        let have = actual.iter().filter(|x| **x == lt).count();
+        let want = expected.iter().filter(|x| **x == lt).count();
+        if have > want {
+            results.push(Discrepancy::ExtraLanes {
+                side,
+                lt,
+                count: have - want,
+            });
+        } else if have < want {
+            results.push(Discrepancy::MissingLanes {
+                side,
+                lt,
+                count: want - have,
+            });
+        }
+        same_counts = same_counts && have == want;
+    }
+    if same_counts && actual != expected {
+        results.push(Discrepancy::DifferentOrder { side });
+    }
+}
+
+// Until widths are tagged, every lane of a type is the same width.
+pub fn default_lane_width(lt: LaneType) -> Distance {
+    if lt == LaneType::Sidewalk {
+        SIDEWALK_THICKNESS
+    } else {
+        NORMAL_LANE_THICKNESS
+    }
+}
+
+// Rough buckets for how much space a road takes up, for urban-form analysis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowClass {
+    // Less than 10m
+    Narrow,
+    // 10m up to 20m
+    Medium,
+    // 20m or more
+    Wide,
+}
+
+// Classifies the total width of a road, like from RoadSpec::total_width.
+pub fn right_of_way_class(total: Distance) -> RowClass {
+    if total < Distance::meters(10.0) {
+        RowClass::Narrow
+    } else if total < Distance::meters(20.0) {
+        RowClass::Medium
+    } else {
+        RowClass::Wide
+    }
+}
+
+// OSM's :lanes suffixes (like turn:lanes:forward) list one value per lane, from the leftmost lane to
+// the rightmost, from the perspective of somebody traveling in that direction. Only lanes for motor
+// vehicles count, and a center turn lane has its own :both_ways suffix. Each side of our lanes
+// starts at the center line and moves towards the curb, so the order is the same; returns the index
+// into side for each OSM lane.
+pub fn osm_lane_numbering(side: &[LaneType]) -> Vec<usize> {
+    side.iter()
+        .enumerate()
+        .filter(|(_, lt)| **lt == LaneType::Driving || **lt == LaneType::Bus)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+// Somewhere a pedestrian could cross from one side of a road to the other
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrossingCandidate {
+    // Indices into RoadSpec's back and fwd
+    pub back_sidewalk: usize,
+    pub fwd_sidewalk: usize,
+    // Between the centers of the two sidewalks
+    pub length: Distance,
+}
+
 // This is a convenient way for map_editor to plumb instructions here.
 #[derive(Serialize, Deserialize)]
 pub struct RoadSpec {
@@ -164,12 +1087,16 @@ pub struct RoadSpec {
 
 impl fmt::Display for RoadSpec {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for lt in &self.fwd {
-            write!(f, "{}", RoadSpec::lt_to_char(*lt))?;
+        let mut slash = false;
+        for (side, _, lt) in self.lanes_iter() {
+            if side == Side::Left && !slash {
+                write!(f, "/")?;
+                slash = true;
+            }
+            write!(f, "{}", RoadSpec::lt_to_char(lt))?;
         }
-        write!(f, "/")?;
-        for lt in &self.back {
-            write!(f, "{}", RoadSpec::lt_to_char(*lt))?;
+        if !slash {
+            write!(f, "/")?;
         }
         Ok(())
     }
@@ -177,6 +1104,24 @@ impl fmt::Display for RoadSpec {
 
 impl RoadSpec {
     pub fn parse(s: String) -> Option<RoadSpec> {
+        let mut unknown = Vec::new();
+        let spec = RoadSpec::parse_skipping(&s, &mut unknown)?;
+        if unknown.is_empty() {
+            Some(spec)
+        } else {
+            None
+        }
+    }
+
+    // Like parse, but skips unrecognized characters (with a warning) instead of giving up. Still
+    // None without a '/', or when no lanes are left.
+    pub fn parse_lenient(s: String) -> Warn<Option<RoadSpec>> {
+        let mut warnings = Vec::new();
+        let result = RoadSpec::parse_skipping(&s, &mut warnings);
+        Warn::warnings(result, warnings)
+    }
+
+    fn parse_skipping(s: &str, warnings: &mut Vec<String>) -> Option<RoadSpec> {
         let mut fwd: Vec<LaneType> = Vec::new();
         let mut back: Vec<LaneType> = Vec::new();
         let mut seen_slash = false;
@@ -190,7 +1135,7 @@ impl RoadSpec {
                     fwd.push(lt);
                 }
             } else {
-                return None;
+                warnings.push(format!("skipping unknown lane {:?} in RoadSpec {}", c, s));
             }
         }
         if seen_slash && (fwd.len() + back.len()) > 0 {
@@ -200,7 +1145,579 @@ impl RoadSpec {
         }
     }
 
-    fn lt_to_char(lt: LaneType) -> char {
+    // A oneway spec from just the fwd side, like "dds". None for an invalid or empty string.
+    pub fn oneway(fwd: &str) -> Option<RoadSpec> {
+        let fwd = fwd
+            .chars()
+            .map(RoadSpec::char_to_lt)
+            .collect::<Option<Vec<LaneType>>>()?;
+        if fwd.is_empty() {
+            return None;
+        }
+        Some(RoadSpec {
+            fwd,
+            back: Vec::new(),
+        })
+    }
+
+    // Both sides are ordered from the centerline outwards, so the outer lane is the one touching
+    // the curb (usually a sidewalk or parking) and the inner lane is the one next to the
+    // centerline. None if that side has no lanes.
+    pub fn outer_lane(&self, side: Side) -> Option<LaneType> {
+        self.lanes(side).last().cloned()
+    }
+
+    pub fn inner_lane(&self, side: Side) -> Option<LaneType> {
+        self.lanes(side).first().cloned()
+    }
+
+    // Only counts lanes carrying moving traffic, so not parking, sidewalks, turn lanes, etc.
+    pub fn total_travel_lanes(&self) -> usize {
+        self.fwd
+            .iter()
+            .chain(self.back.iter())
+            .filter(|lt| lt.is_for_moving_vehicles())
+            .count()
+    }
+
+    // A rough estimate of how many vehicles per hour can travel on one side. Each driving lane
+    // carries about 1900 vehicles an hour at 50mph or more, and proportionally fewer on slower
+    // roads. Ignores intersections, which are usually the real bottleneck.
+    pub fn estimated_capacity_vph(&self, side: Side, speed_limit: Speed) -> f64 {
+        let per_lane = 1900.0
+            * (speed_limit.inner_meters_per_second()
+                / Speed::miles_per_hour(50.0).inner_meters_per_second())
+            .min(1.0);
+        let num_driving = self
+            .lanes(side)
+            .iter()
+            .filter(|lt| **lt == LaneType::Driving)
+            .count();
+        per_lane * (num_driving as f64)
+    }
+
+    // Should a center line be painted between the two directions? Only when motor vehicles travel
+    // right next to each other in opposite directions. Anything else in the middle (a median, a
+    // shared turn lane, or shoulders on a divided road) already separates the directions.
+    pub fn requires_center_line(&self) -> bool {
+        match (self.inner_lane(Side::Right), self.inner_lane(Side::Left)) {
+            (Some(fwd), Some(back)) => fwd.is_vehicle_lane() && back.is_vehicle_lane(),
+            _ => false,
+        }
+    }
+
+    // A copy without any cosmetic lanes, for comparing against specs derived from simple tags.
+    pub fn strip_cosmetic(&self) -> RoadSpec {
+        RoadSpec {
+            fwd: self
+                .fwd
+                .iter()
+                .filter(|lt| !lt.is_cosmetic())
+                .cloned()
+                .collect(),
+            back: self
+                .back
+                .iter()
+                .filter(|lt| !lt.is_cosmetic())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn has_lane(&self, lt: LaneType) -> bool {
+        self.fwd.contains(&lt) || self.back.contains(&lt)
+    }
+
+    // Do both specs have the same number of lanes for each way of traveling, in each direction?
+    // Ordering and lanes that nobody travels along (like parking) don't matter.
+    pub fn approx_eq_travel(&self, other: &RoadSpec) -> bool {
+        fn counts(lanes: &[LaneType]) -> Vec<usize> {
+            vec![
+                LaneType::Driving,
+                LaneType::Bus,
+                LaneType::Biking,
+                LaneType::Sidewalk,
+            ]
+            .into_iter()
+            .map(|lt| lanes.iter().filter(|x| **x == lt).count())
+            .collect()
+        }
+        counts(&self.fwd) == counts(&other.fwd) && counts(&self.back) == counts(&other.back)
+    }
+
+    // Every lane from the left curb to the right one (so the back side reversed, then the fwd
+    // side), along with the distance from the left curb to the center of that lane.
+    pub fn split_at_width(&self) -> Vec<(LaneType, Distance)> {
+        let mut result = Vec::new();
+        let mut offset = Distance::ZERO;
+        for lt in self.back.iter().rev().chain(self.fwd.iter()) {
+            let width = default_lane_width(*lt);
+            result.push((*lt, offset + width / 2.0));
+            offset += width;
+        }
+        result
+    }
+
+    // Every pair of sidewalks on opposite sides of the road, which is where a crossing could go.
+    // Nothing if there's a sidewalk on at most one side.
+    pub fn pedestrian_crossing_points(&self) -> Vec<CrossingCandidate> {
+        let offsets = self.split_at_width();
+        let mut result = Vec::new();
+        for (back_idx, back_lt) in self.back.iter().enumerate() {
+            for (fwd_idx, fwd_lt) in self.fwd.iter().enumerate() {
+                if *back_lt == LaneType::Sidewalk && *fwd_lt == LaneType::Sidewalk {
+                    let from = offsets[self.back.len() - 1 - back_idx].1;
+                    let to = offsets[self.back.len() + fwd_idx].1;
+                    result.push(CrossingCandidate {
+                        back_sidewalk: back_idx,
+                        fwd_sidewalk: fwd_idx,
+                        length: to - from,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    // How far from the left curb is the boundary between the two directions of travel? When the
+    // sides have different widths, this isn't the middle of the road.
+    pub fn centerline_offset(&self) -> Distance {
+        self.back.iter().map(|lt| default_lane_width(*lt)).sum()
+    }
+
+    // The total width from curb to curb
+    pub fn total_width(&self) -> Distance {
+        self.back
+            .iter()
+            .chain(self.fwd.iter())
+            .map(|lt| default_lane_width(*lt))
+            .sum()
+    }
+
+    // The final width of each lane when the road is exactly total wide, in the same left-to-right
+    // order as split_at_width. Lanes start at their default width. A narrower road squeezes every
+    // lane proportionally. A wider one gives the extra space to the lanes with moving traffic, or
+    // to everything if there are none.
+    pub fn width_profile(&self, total: Distance) -> Vec<(LaneType, Distance)> {
+        let lanes: Vec<LaneType> = self
+            .back
+            .iter()
+            .rev()
+            .chain(self.fwd.iter())
+            .cloned()
+            .collect();
+        if lanes.is_empty() {
+            return Vec::new();
+        }
+        let total = total.max(Distance::ZERO);
+        let default_total = self.total_width();
+        if total <= default_total {
+            let ratio = total / default_total;
+            return lanes
+                .into_iter()
+                .map(|lt| (lt, default_lane_width(lt) * ratio))
+                .collect();
+        }
+
+        let mut flexible: Vec<bool> = lanes.iter().map(|lt| lt.is_for_moving_vehicles()).collect();
+        if !flexible.contains(&true) {
+            flexible = vec![true; lanes.len()];
+        }
+        let flexible_width: Distance = lanes
+            .iter()
+            .zip(flexible.iter())
+            .filter(|(_, flex)| **flex)
+            .map(|(lt, _)| default_lane_width(*lt))
+            .sum();
+        let extra = total - default_total;
+        lanes
+            .into_iter()
+            .zip(flexible)
+            .map(|(lt, flex)| {
+                let width = default_lane_width(lt);
+                if flex {
+                    (lt, width + extra * (width / flexible_width))
+                } else {
+                    (lt, width)
+                }
+            })
+            .collect()
+    }
+
+    // Like width_profile, but from the tagged widths. When width:carriageway is known, it bounds
+    // the lanes with moving traffic, and only the rest of the total width goes to sidewalks,
+    // parking, and everything else. Without any widths tagged, there's nothing to fit to.
+    pub fn tagged_width_profile(&self, widths: &RoadWidths) -> Option<Vec<(LaneType, Distance)>> {
+        let carriageway = match (widths.total, widths.carriageway) {
+            (None, None) => {
+                return None;
+            }
+            (Some(total), None) => {
+                return Some(self.width_profile(total));
+            }
+            (_, Some(carriageway)) => carriageway.max(Distance::ZERO),
+        };
+
+        let lanes: Vec<LaneType> = self
+            .back
+            .iter()
+            .rev()
+            .chain(self.fwd.iter())
+            .cloned()
+            .collect();
+        let default_width = |moving: bool| -> Distance {
+            lanes
+                .iter()
+                .filter(|lt| lt.is_for_moving_vehicles() == moving)
+                .map(|lt| default_lane_width(*lt))
+                .sum()
+        };
+        let default_carriageway = default_width(true);
+        if default_carriageway == Distance::ZERO {
+            // Nothing drives here, so the carriageway width doesn't describe any lanes.
+            return widths.total.map(|total| self.width_profile(total));
+        }
+        let default_rest = default_width(false);
+        let rest = match widths.total {
+            Some(total) => (total - carriageway).max(Distance::ZERO),
+            None => default_rest,
+        };
+
+        Some(
+            lanes
+                .into_iter()
+                .map(|lt| {
+                    let width = default_lane_width(lt);
+                    if lt.is_for_moving_vehicles() {
+                        (lt, carriageway * (width / default_carriageway))
+                    } else {
+                        (lt, rest * (width / default_rest))
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    pub fn right_of_way_class(&self) -> RowClass {
+        right_of_way_class(self.total_width())
+    }
+
+    // Look up a lane by its index in the full cross-section, in the same left-to-right order as
+    // split_at_width. Returns the side of the road and the index into that side's lanes, which run
+    // from the center to the curb.
+    pub fn lane_at_cross_section_index(&self, idx: usize) -> Option<(Side, usize, LaneType)> {
+        if idx < self.back.len() {
+            let i = self.back.len() - 1 - idx;
+            Some((Side::Left, i, self.back[i]))
+        } else {
+            let i = idx - self.back.len();
+            self.fwd.get(i).map(|lt| (Side::Right, i, *lt))
+        }
+    }
+
+    // Without a :forward or :backward suffix, a :lanes tag on a two-way road covers every motor
+    // vehicle lane, left to right in the direction the way is drawn: the back side (from the curb
+    // in), any center turn lane, and then the fwd side. On a oneway, it's just the side with
+    // traffic, matching osm_lane_numbering.
+    pub fn osm_lane_numbering(&self) -> Vec<(Side, usize)> {
+        if self.fwd.is_empty() {
+            return osm_lane_numbering(&self.back)
+                .into_iter()
+                .map(|idx| (Side::Left, idx))
+                .collect();
+        }
+        let mut result: Vec<(Side, usize)> = osm_lane_numbering(&self.back)
+            .into_iter()
+            .rev()
+            .map(|idx| (Side::Left, idx))
+            .collect();
+        if self.fwd[0] == LaneType::SharedLeftTurn {
+            result.push((Side::Right, 0));
+        }
+        result.extend(
+            osm_lane_numbering(&self.fwd)
+                .into_iter()
+                .map(|idx| (Side::Right, idx)),
+        );
+        result
+    }
+
+    // Reverse the direction of travel, but keep parking, sidewalks, and shoulders on the same
+    // physical side of the road. Only the lanes between the center and those curb features get
+    // swapped, and a center turn lane stays in the center. This differs from just swapping fwd
+    // and back (like map_editor does), which mirrors the entire road, curb features included.
+    pub fn swap_sides_preserving_curb_features(&mut self) {
+        fn split_curb(side: &mut Vec<LaneType>) -> Vec<LaneType> {
+            let idx = side
+                .iter()
+                .rposition(|lt| {
+                    !matches!(
+                        lt,
+                        LaneType::Parking | LaneType::Sidewalk | LaneType::Shoulder
+                    )
+                })
+                .map(|idx| idx + 1)
+                .unwrap_or(0);
+            side.split_off(idx)
+        }
+
+        let fwd_curb = split_curb(&mut self.fwd);
+        let back_curb = split_curb(&mut self.back);
+        let center = if self.fwd.first() == Some(&LaneType::SharedLeftTurn) {
+            Some(self.fwd.remove(0))
+        } else {
+            None
+        };
+
+        std::mem::swap(&mut self.fwd, &mut self.back);
+        if let Some(lt) = center {
+            self.fwd.insert(0, lt);
+        }
+        self.fwd.extend(fwd_curb);
+        self.back.extend(back_curb);
+    }
+
+    // A cross-section for debugging, reading from the left curb to the right one, like
+    // "|S|P|D D|=|D D|P|S|". Neighboring lanes of the same type share a cell, and "=" marks the
+    // centerline. Oneways don't have a centerline.
+    pub fn to_ascii_diagram(&self) -> String {
+        fn cells(lanes: &[LaneType], result: &mut Vec<String>) {
+            let mut last: Option<LaneType> = None;
+            for lt in lanes {
+                let c = RoadSpec::lt_to_char(*lt).to_ascii_uppercase().to_string();
+                if last == Some(*lt) {
+                    let cell = result.last_mut().unwrap();
+                    cell.push(' ');
+                    cell.push_str(&c);
+                } else {
+                    result.push(c);
+                }
+                last = Some(*lt);
+            }
+        }
+
+        let mut result = Vec::new();
+        cells(
+            &self.back.iter().rev().cloned().collect::<Vec<_>>(),
+            &mut result,
+        );
+        if !self.fwd.is_empty() && !self.back.is_empty() {
+            result.push("=".to_string());
+        }
+        cells(&self.fwd, &mut result);
+        format!("|{}|", result.join("|"))
+    }
+
+    // Catches specs that don't make sense for a class of road, like a motorway with parking.
+    pub fn is_valid_for_class(&self, class: RoadClass) -> Result<(), String> {
+        let banned: &[LaneType] = match class {
+            RoadClass::Motorway => &[
+                LaneType::Parking,
+                LaneType::Sidewalk,
+                LaneType::Biking,
+                LaneType::SharedLeftTurn,
+            ],
+            RoadClass::Trunk => &[LaneType::Parking],
+            _ => &[],
+        };
+        for lt in banned {
+            if self.has_lane(*lt) {
+                return Err(format!(
+                    "{:?} roads can't have a {}",
+                    class,
+                    lt.short_name()
+                ));
+            }
+        }
+        if !self.has_lane(LaneType::Driving) && !self.has_lane(LaneType::Construction) {
+            return Err(format!("{:?} roads need a driving lane", class));
+        }
+        Ok(())
+    }
+
+    // Sentences like "Added a bike lane on the right", for the editor's history of edits
+    pub fn describe_changes_from(&self, old: &RoadSpec) -> Vec<String> {
+        fn lanes(lt: LaneType, count: usize) -> String {
+            if count == 1 {
+                format!("a {}", lt.short_name())
+            } else {
+                format!("{} {}s", count, lt.short_name())
+            }
+        }
+
+        let mut diffs = Vec::new();
+        compare_side(Side::Right, &self.fwd, &old.fwd, &mut diffs);
+        compare_side(Side::Left, &self.back, &old.back, &mut diffs);
+        diffs
+            .into_iter()
+            .map(|diff| match diff {
+                Discrepancy::ExtraLanes { side, lt, count } => {
+                    format!("Added {} on the {}", lanes(lt, count), side.as_str())
+                }
+                Discrepancy::MissingLanes { side, lt, count } => {
+                    format!("Removed {} on the {}", lanes(lt, count), side.as_str())
+                }
+                Discrepancy::DifferentOrder { side } => {
+                    format!("Rearranged the lanes on the {}", side.as_str())
+                }
+            })
+            .collect()
+    }
+
+    // Edits can leave parking stranded between driving lanes, like "dpd". On each side, move any
+    // parking lane inside the outermost driving lane to just past it, so "dpds" becomes "ddps".
+    // Nothing else moves. Returns true if the spec changed.
+    pub fn merge_adjacent_driving(&mut self) -> bool {
+        fn merge(side: &mut Vec<LaneType>) -> bool {
+            let last_driving = match side.iter().rposition(|lt| *lt == LaneType::Driving) {
+                Some(idx) => idx,
+                None => {
+                    return false;
+                }
+            };
+            let stray = side[..last_driving]
+                .iter()
+                .filter(|lt| **lt == LaneType::Parking)
+                .count();
+            if stray == 0 {
+                return false;
+            }
+            let mut result: Vec<LaneType> = side[..=last_driving]
+                .iter()
+                .filter(|lt| **lt != LaneType::Parking)
+                .cloned()
+                .collect();
+            result.extend(iter::repeat(LaneType::Parking).take(stray));
+            result.extend_from_slice(&side[last_driving + 1..]);
+            *side = result;
+            true
+        }
+
+        let fwd = merge(&mut self.fwd);
+        let back = merge(&mut self.back);
+        fwd || back
+    }
+
+    // Parking and turn lanes are useless without a lane to reach them from. If a spec has them but
+    // nothing vehicles can travel on (driving, bus, or construction), add a driving lane next to the
+    // center of the fwd side (or the back side, if only it has lanes). Specs without vehicles by
+    // design, like a footway "s/s" or a busway "u/u", are left alone. Returns true if the spec
+    // changed.
+    pub fn ensure_min_driving_lane(&mut self) -> bool {
+        let has_any = |types: &[LaneType]| types.iter().any(|lt| self.has_lane(*lt));
+        if has_any(&[LaneType::Driving, LaneType::Bus, LaneType::Construction])
+            || !has_any(&[LaneType::Parking, LaneType::SharedLeftTurn])
+        {
+            return false;
+        }
+        let side = if self.fwd.is_empty() && !self.back.is_empty() {
+            &mut self.back
+        } else {
+            &mut self.fwd
+        };
+        let idx = if side.first() == Some(&LaneType::SharedLeftTurn) {
+            1
+        } else {
+            0
+        };
+        side.insert(idx, LaneType::Driving);
+        true
+    }
+
+    // How many lanes of any type are on one side? Unlike total_travel_lanes, this counts parking,
+    // sidewalks, etc.
+    pub fn num_lanes(&self, side: Side) -> usize {
+        self.lanes(side).len()
+    }
+
+    // Every lane, first the fwd side and then the back, each from the center to the curb. The
+    // index is into that side's lanes.
+    pub fn lanes_iter(&self) -> impl Iterator<Item = (Side, usize, LaneType)> + '_ {
+        let fwd = self
+            .fwd
+            .iter()
+            .enumerate()
+            .map(|(idx, lt)| (Side::Right, idx, *lt));
+        let back = self
+            .back
+            .iter()
+            .enumerate()
+            .map(|(idx, lt)| (Side::Left, idx, *lt));
+        fwd.chain(back)
+    }
+
+    pub fn has_sidewalk(&self, side: Side) -> bool {
+        self.lanes(side).contains(&LaneType::Sidewalk)
+    }
+
+    // Where are the Driving lanes within one side's lanes?
+    pub fn driving_lane_indices(&self, side: Side) -> Vec<usize> {
+        self.lanes(side)
+            .iter()
+            .enumerate()
+            .filter(|(_, lt)| **lt == LaneType::Driving)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    // Add lanes to the curb end of one side of the road. This is useful when merging road segments.
+    pub fn append_side(&mut self, side: Side, lanes: &[LaneType]) {
+        match side {
+            Side::Right => self.fwd.extend_from_slice(lanes),
+            Side::Left => self.back.extend_from_slice(lanes),
+        }
+    }
+
+    // Deterministically produces a plausible spec, for fuzzing anything that consumes RoadSpecs.
+    // Each side is ordered like real roads: travel lanes, then maybe a bike lane, then parking or
+    // a shoulder, then a sidewalk. There's always at least one driving lane.
+    #[cfg(any(test, feature = "fuzz"))]
+    pub fn random(seed: u64) -> RoadSpec {
+        use rand::{Rng, SeedableRng};
+
+        fn random_side(rng: &mut rand_xorshift::XorShiftRng) -> Vec<LaneType> {
+            let mut side = vec![LaneType::Driving; rng.gen_range(1, 4)];
+            if rng.gen_bool(0.2) {
+                side.push(LaneType::Bus);
+            }
+            if rng.gen_bool(0.3) {
+                side.push(LaneType::Biking);
+            }
+            if rng.gen_bool(0.1) {
+                side.push(LaneType::Shoulder);
+                return side;
+            }
+            if rng.gen_bool(0.4) {
+                side.push(LaneType::Parking);
+            }
+            if rng.gen_bool(0.8) {
+                side.push(LaneType::Sidewalk);
+            }
+            side
+        }
+
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(seed);
+        let mut fwd = random_side(&mut rng);
+        let back = if rng.gen_bool(0.2) {
+            Vec::new()
+        } else {
+            random_side(&mut rng)
+        };
+        if !back.is_empty() && rng.gen_bool(0.1) {
+            fwd.insert(0, LaneType::SharedLeftTurn);
+        }
+        RoadSpec { fwd, back }
+    }
+
+    // The right side is fwd.
+    fn lanes(&self, side: Side) -> &Vec<LaneType> {
+        match side {
+            Side::Right => &self.fwd,
+            Side::Left => &self.back,
+        }
+    }
+
+    pub(crate) fn lt_to_char(lt: LaneType) -> char {
         match lt {
             LaneType::Driving => 'd',
             LaneType::Parking => 'p',
@@ -209,6 +1726,9 @@ impl RoadSpec {
             LaneType::Bus => 'u',
             LaneType::SharedLeftTurn => 'l',
             LaneType::Construction => 'c',
+            LaneType::Shoulder => 'h',
+            LaneType::Buffer => 'v',
+            LaneType::Median => 'm',
         }
     }
 
@@ -221,7 +1741,1858 @@ impl RoadSpec {
             'u' => Some(LaneType::Bus),
             'l' => Some(LaneType::SharedLeftTurn),
             'c' => Some(LaneType::Construction),
+            'h' => Some(LaneType::Shoulder),
+            'v' => Some(LaneType::Buffer),
+            'm' => Some(LaneType::Median),
             _ => None,
         }
     }
 }
+
+// Describes the characters RoadSpec::parse understands, so tools outside A/B Street can stay in
+// sync without copying the table.
+#[derive(Debug, Serialize)]
+pub struct RoadSpecSchema {
+    // Between the fwd and back sides
+    pub separator: char,
+    pub lanes: Vec<LaneCode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LaneCode {
+    pub lane_type: LaneType,
+    pub code: char,
+    pub description: &'static str,
+}
+
+impl RoadSpecSchema {
+    pub fn current() -> RoadSpecSchema {
+        RoadSpecSchema {
+            separator: '/',
+            lanes: LaneType::all()
+                .into_iter()
+                .map(|lt| LaneCode {
+                    lane_type: lt,
+                    code: RoadSpec::lt_to_char(lt),
+                    description: lt.describe(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        abstutil::to_json(self)
+    }
+}
+
+// RoadSpec's characters are English mnemonics. Editors in other languages can show their own,
+// while RoadSpec::parse and the synthetic tags always use the English ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecLocale {
+    English,
+    German,
+}
+
+impl SpecLocale {
+    // Takes a language code, like "de"
+    pub fn from_code(code: &str) -> Option<SpecLocale> {
+        match code {
+            "en" => Some(SpecLocale::English),
+            "de" => Some(SpecLocale::German),
+            _ => None,
+        }
+    }
+
+    fn lt_to_char(self, lt: LaneType) -> char {
+        match self {
+            SpecLocale::English => RoadSpec::lt_to_char(lt),
+            SpecLocale::German => match lt {
+                // Fahrstreifen
+                LaneType::Driving => 'f',
+                // Parkstreifen
+                LaneType::Parking => 'p',
+                // Gehweg
+                LaneType::Sidewalk => 'g',
+                // Radfahrstreifen
+                LaneType::Biking => 'r',
+                // Busspur
+                LaneType::Bus => 'b',
+                // Linksabbieger
+                LaneType::SharedLeftTurn => 'l',
+                // Baustelle
+                LaneType::Construction => 'x',
+                // Seitenstreifen
+                LaneType::Shoulder => 's',
+                // Sperrfläche
+                LaneType::Buffer => 'z',
+                // Mittelstreifen
+                LaneType::Median => 'm',
+            },
+        }
+    }
+
+    fn char_to_lt(self, c: char) -> Option<LaneType> {
+        LaneType::all()
+            .into_iter()
+            .find(|lt| self.lt_to_char(*lt) == c)
+    }
+
+    // Translates a spec like "fprg/fg" written in this locale to the canonical form. None if any
+    // character isn't a lane or '/'.
+    pub fn to_canonical(self, spec: &str) -> Option<String> {
+        spec.chars()
+            .map(|c| {
+                if c == '/' {
+                    Some(c)
+                } else {
+                    self.char_to_lt(c).map(RoadSpec::lt_to_char)
+                }
+            })
+            .collect()
+    }
+
+    // The opposite of to_canonical
+    pub fn from_canonical(self, spec: &str) -> Option<String> {
+        spec.chars()
+            .map(|c| {
+                if c == '/' {
+                    Some(c)
+                } else {
+                    RoadSpec::char_to_lt(c).map(|lt| self.lt_to_char(lt))
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        advisory_speed_limit, compare_to_osm, consumed_tags, default_lane_width, get_lane_types,
+        get_lane_types_batch, get_lane_types_with_policy, has_parking, is_area, is_dead_end,
+        is_oneway, is_reversed_oneway, lane_types, osm_lane_numbering, right_of_way_class,
+        synthesize_defaults, uses_sidepath, CircularJunction, CrossingCandidate, Discrepancy,
+        LaneInferencePolicy, ParsedTags, RoadClass, RoadLabels, RoadSpec, RoadSpecSchema,
+        RoadWidths, RowClass, Side, SizeLimits, SpecLocale, Structure,
+    };
+    use crate::{LaneType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
+    use geom::{Distance, Speed};
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    fn tags(kv: Vec<&str>) -> BTreeMap<String, String> {
+        let mut tags = BTreeMap::new();
+        for pair in kv {
+            let parts = pair.split('=').collect::<Vec<_>>();
+            tags.insert(parts[0].to_string(), parts[1].to_string());
+        }
+        tags
+    }
+
+    // Renders lanes in the same compact form RoadSpec uses, like "ddps"
+    fn lanes_to_string(lanes: &[LaneType]) -> String {
+        lanes.iter().map(|lt| RoadSpec::lt_to_char(*lt)).collect()
+    }
+
+    fn assert_lanes(expected: &str, actual: &[LaneType]) {
+        let actual = lanes_to_string(actual);
+        assert_eq!(
+            expected, actual,
+            "got {:?}, expected {:?}",
+            actual, expected
+        );
+    }
+
+    #[test]
+    fn test_outer_inner_lanes() {
+        let spec = RoadSpec::parse("ldps/ds".to_string()).unwrap();
+        assert_eq!(Some(LaneType::Sidewalk), spec.outer_lane(Side::Right));
+        assert_eq!(Some(LaneType::SharedLeftTurn), spec.inner_lane(Side::Right));
+        assert_eq!(Some(LaneType::Sidewalk), spec.outer_lane(Side::Left));
+        assert_eq!(Some(LaneType::Driving), spec.inner_lane(Side::Left));
+
+        let oneway = RoadSpec::parse("db/".to_string()).unwrap();
+        assert_eq!(Some(LaneType::Biking), oneway.outer_lane(Side::Right));
+        assert_eq!(None, oneway.outer_lane(Side::Left));
+        assert_eq!(None, oneway.inner_lane(Side::Left));
+    }
+
+    #[test]
+    fn test_total_travel_lanes() {
+        let spec = RoadSpec::parse("ldbps/dups".to_string()).unwrap();
+        assert_eq!(4, spec.total_travel_lanes());
+        assert_eq!(
+            0,
+            RoadSpec::parse("s/s".to_string())
+                .unwrap()
+                .total_travel_lanes()
+        );
+    }
+
+    #[test]
+    fn test_has_lane() {
+        let spec = RoadSpec::parse("dps/dbs".to_string()).unwrap();
+        assert!(spec.has_lane(LaneType::Biking));
+        assert!(spec.has_lane(LaneType::Parking));
+        assert!(!spec.has_lane(LaneType::Bus));
+    }
+
+    #[test]
+    fn test_approx_eq_travel() {
+        let spec = |x: &str| RoadSpec::parse(x.to_string()).unwrap();
+        assert!(spec("dbps/ds").approx_eq_travel(&spec("dbs/ds")));
+        assert!(spec("dbps/ds").approx_eq_travel(&spec("bdps/ds")));
+        assert!(!spec("dbps/ds").approx_eq_travel(&spec("dups/ds")));
+        assert!(!spec("dbps/ds").approx_eq_travel(&spec("ds/dbps")));
+        assert!(!spec("dds/").approx_eq_travel(&spec("ds/ds")));
+    }
+
+    #[test]
+    fn test_directional_lane_counts() {
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec![
+                "highway=primary",
+                "lanes=3",
+                "lanes:forward=2",
+                "lanes:backward=2",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("dd", &fwd);
+        assert_lanes("dd", &back);
+        assert_eq!(1, warnings.len());
+
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec!["highway=primary", "lanes=4", "lanes:forward=3"]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("ddd", &fwd);
+        assert_lanes("d", &back);
+        assert!(warnings.is_empty());
+
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec![
+                "highway=primary",
+                "lanes=5",
+                "lanes:backward=2",
+                "lanes:both_ways=1",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("ldd", &fwd);
+        assert_lanes("dd", &back);
+        assert!(warnings.is_empty());
+
+        // No turn:lanes:both_ways needed
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec!["highway=primary", "lanes=3", "lanes:both_ways=1"]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("ld", &fwd);
+        assert_lanes("d", &back);
+        assert!(warnings.is_empty());
+
+        // The center lane comes out of the total before splitting it
+        for (lanes, expected_fwd, expected_back) in
+            &[("lanes=5", "ldd", "dd"), ("lanes=4", "ld", "d")]
+        {
+            let mut warnings = Vec::new();
+            let (fwd, back) = lane_types(
+                &tags(vec!["highway=primary", lanes, "lanes:both_ways=1"]),
+                &LaneInferencePolicy::default(),
+                &mut warnings,
+            );
+            assert_lanes(expected_fwd, &fwd);
+            assert_lanes(expected_back, &back);
+            assert!(warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_center_cycle_track() {
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=secondary",
+            "lanes=2",
+            "cycleway=track",
+            "cycleway:placement=middle",
+            "sidewalk=both",
+        ]));
+        assert_lanes("bds", &fwd);
+        assert_lanes("bds", &back);
+
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=secondary",
+            "lanes=3",
+            "centre_turn_lane=yes",
+            "cycleway=track",
+            "cycleway:placement=middle",
+            "sidewalk=both",
+        ]));
+        assert_lanes("lbds", &fwd);
+        assert_lanes("bds", &back);
+
+        // Without the placement, it's an ordinary curbside lane
+        let (fwd, _) = get_lane_types(&tags(vec![
+            "highway=secondary",
+            "cycleway=lane",
+            "sidewalk=both",
+        ]));
+        assert_lanes("dbs", &fwd);
+    }
+
+    #[test]
+    fn test_use_sidepath() {
+        let mut warnings = Vec::new();
+        let osm_tags = tags(vec![
+            "highway=primary",
+            "bicycle=use_sidepath",
+            "cycleway=lane",
+            "sidewalk=both",
+        ]);
+        let (fwd, back) = lane_types(&osm_tags, &LaneInferencePolicy::default(), &mut warnings);
+        assert_lanes("ds", &fwd);
+        assert_lanes("ds", &back);
+        assert_eq!(1, warnings.len());
+        assert!(uses_sidepath(&osm_tags));
+
+        let mut warnings = Vec::new();
+        let (fwd, _) = lane_types(
+            &tags(vec![
+                "highway=primary",
+                "bicycle=use_sidepath",
+                "sidewalk=both",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("ds", &fwd);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_cycleway_no() {
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec!["highway=residential", "cycleway=no", "sidewalk=both"]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("ds", &fwd);
+        assert_lanes("ds", &back);
+        assert!(warnings.is_empty());
+
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec![
+                "highway=residential",
+                "cycleway=no",
+                "cycleway:right=lane",
+                "sidewalk=both",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("dbs", &fwd);
+        assert_lanes("ds", &back);
+        assert_eq!(1, warnings.len());
+    }
+
+    #[test]
+    fn test_oneway_with_backward_lanes() {
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec![
+                "highway=primary",
+                "oneway=yes",
+                "lanes=3",
+                "lanes:backward=1",
+                "sidewalk=none",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("dd", &fwd);
+        assert_lanes("d", &back);
+        assert_eq!(1, warnings.len());
+
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec![
+                "highway=primary",
+                "oneway=-1",
+                "lanes=3",
+                "lanes:forward=1",
+                "sidewalk=none",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("d", &fwd);
+        assert_lanes("dd", &back);
+        assert_eq!(
+            vec!["oneway=-1, but lanes:forward=1; treating the road as two-way".to_string()],
+            warnings
+        );
+    }
+
+    #[test]
+    fn test_untagged_ways() {
+        for (class, hwy, expected) in &[
+            (RoadClass::Residential, "residential", "dps/dps"),
+            (RoadClass::Primary, "primary", "dds/dds"),
+            (RoadClass::Service, "service", "d/d"),
+        ] {
+            assert_eq!(Some(*class), RoadClass::from_highway(hwy));
+            assert_eq!(
+                *expected,
+                synthesize_defaults(*class, false, &LaneInferencePolicy::default()).to_string()
+            );
+            let (fwd, back) = get_lane_types(&tags(vec![&format!("highway={}", hwy), "name=Foo"]));
+            assert_eq!(*expected, RoadSpec { fwd, back }.to_string());
+        }
+
+        // Motorways and their links are implicitly oneway
+        for hwy in &["motorway", "motorway_link"] {
+            let (fwd, back) = get_lane_types(&tags(vec![&format!("highway={}", hwy)]));
+            assert_lanes("dd", &fwd);
+            assert!(back.is_empty());
+        }
+        assert_eq!(
+            "dd/",
+            synthesize_defaults(RoadClass::Motorway, true, &LaneInferencePolicy::default())
+                .to_string()
+        );
+
+        // Any lane tagging at all means the normal rules apply
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=residential", "sidewalk=none"]));
+        assert_eq!("d/d", RoadSpec { fwd, back }.to_string());
+    }
+
+    #[test]
+    fn test_inference_policy() {
+        let policy = LaneInferencePolicy {
+            default_lanes_per_direction: 2,
+            ..Default::default()
+        };
+        let (fwd, back) =
+            get_lane_types_with_policy(&tags(vec!["highway=primary", "sidewalk=both"]), &policy);
+        assert_eq!("dds/dds", RoadSpec { fwd, back }.to_string());
+
+        let mut policy = LaneInferencePolicy {
+            untagged_sidewalks: false,
+            ..Default::default()
+        };
+        let (fwd, back) = get_lane_types_with_policy(&tags(vec!["highway=residential"]), &policy);
+        assert_eq!("dp/dp", RoadSpec { fwd, back }.to_string());
+
+        policy.untagged_residential_parking = false;
+        assert_eq!(
+            "d/d",
+            synthesize_defaults(RoadClass::Residential, false, &policy).to_string()
+        );
+        assert_eq!(
+            "dd/dd",
+            synthesize_defaults(RoadClass::Primary, false, &policy).to_string()
+        );
+    }
+
+    #[test]
+    fn test_road_labels() {
+        let osm_tags = tags(vec!["highway=motorway", "name=Interstate 5", "ref=I 5"]);
+        assert_eq!(
+            RoadLabels {
+                name: Some("Interstate 5".to_string()),
+                route_ref: Some("I 5".to_string()),
+            },
+            RoadLabels::parse(&osm_tags)
+        );
+        // Labels don't change the lanes
+        assert_eq!(
+            get_lane_types(&tags(vec!["highway=motorway"])),
+            get_lane_types(&osm_tags)
+        );
+
+        let labels = RoadLabels::parse(&tags(vec!["highway=residential", "name="]));
+        assert_eq!(None, labels.name);
+        assert_eq!(None, labels.route_ref);
+    }
+
+    #[test]
+    fn test_structure() {
+        let tunnel = Structure::parse(&tags(vec!["highway=primary", "tunnel=yes", "layer=-1"]));
+        assert!(tunnel.tunnel);
+        assert!(!tunnel.bridge);
+        assert_eq!(-1, tunnel.layer);
+
+        let bridge = Structure::parse(&tags(vec!["highway=primary", "bridge=yes"]));
+        assert!(bridge.bridge);
+        assert!(!bridge.tunnel);
+        assert_eq!(0, bridge.layer);
+
+        let ground = Structure::parse(&tags(vec!["highway=primary", "bridge=no", "cutting=yes"]));
+        assert!(!ground.bridge);
+        assert!(ground.cutting);
+        assert!(!ground.embankment);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let limits = SizeLimits::parse(&tags(vec![
+            "highway=primary",
+            "maxheight=3.5",
+            "maxweight=7.5",
+        ]));
+        assert_eq!(Some(Distance::meters(3.5)), limits.max_height);
+        assert_eq!(Some(7.5), limits.max_weight);
+        assert_eq!(None, limits.max_width);
+
+        let limits = SizeLimits::parse(&tags(vec![
+            "highway=primary",
+            "maxheight=default",
+            "maxwidth=6'6\"",
+        ]));
+        assert_eq!(None, limits.max_height);
+        assert_eq!(Some(Distance::inches(78.0)), limits.max_width);
+    }
+
+    #[test]
+    fn test_oneway_bike_paths() {
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=cycleway"]));
+        assert_lanes("b", &fwd);
+        assert_lanes("b", &back);
+
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=cycleway", "oneway:bicycle=yes"]));
+        assert_lanes("b", &fwd);
+        assert!(back.is_empty());
+
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=cycleway", "oneway:bicycle=-1"]));
+        assert!(fwd.is_empty());
+        assert_lanes("b", &back);
+
+        // The specific tag beats oneway
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=cycleway",
+            "oneway=yes",
+            "oneway:bicycle=no",
+        ]));
+        assert_lanes("b", &fwd);
+        assert_lanes("b", &back);
+
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=path",
+            "foot=designated",
+            "bicycle=designated",
+            "segregated=yes",
+            "oneway:bicycle=yes",
+        ]));
+        assert_lanes("bs", &fwd);
+        assert!(back.is_empty());
+    }
+
+    #[test]
+    fn test_random_specs() {
+        for seed in 0..500 {
+            let spec = RoadSpec::random(seed);
+            assert_eq!(spec.to_string(), RoadSpec::random(seed).to_string());
+            let parsed = RoadSpec::parse(spec.to_string()).unwrap();
+            assert_eq!(spec.to_string(), parsed.to_string());
+
+            assert!(spec.has_lane(LaneType::Driving));
+            for side in &[&spec.fwd, &spec.back] {
+                if let Some(idx) = side.iter().position(|lt| *lt == LaneType::Sidewalk) {
+                    assert_eq!(idx, side.len() - 1, "{}", spec);
+                }
+                if let Some(idx) = side.iter().position(|lt| *lt == LaneType::SharedLeftTurn) {
+                    assert_eq!(0, idx, "{}", spec);
+                }
+            }
+            assert!(!spec.back.contains(&LaneType::SharedLeftTurn), "{}", spec);
+        }
+    }
+
+    // Parking is a frequent source of bugs, since the tags name a physical side of the way, but
+    // oneways build their lanes from the perspective of traffic. The right side of the way is
+    // always fwd here.
+    #[test]
+    fn test_parking_sides() {
+        for (oneway, parking, expected_fwd, expected_back) in vec![
+            (None, "right", "dp", "d"),
+            (None, "left", "d", "dp"),
+            (None, "both", "dp", "dp"),
+            (Some("yes"), "right", "ddp", ""),
+            (Some("yes"), "left", "dd", "p"),
+            (Some("yes"), "both", "ddp", "p"),
+            (Some("-1"), "right", "p", "dd"),
+            (Some("-1"), "left", "", "ddp"),
+            (Some("-1"), "both", "p", "ddp"),
+        ] {
+            let mut input = vec![
+                "highway=residential".to_string(),
+                "lanes=2".to_string(),
+                "sidewalk=none".to_string(),
+                format!("parking:lane:{}=parallel", parking),
+            ];
+            if let Some(oneway) = oneway {
+                input.push(format!("oneway={}", oneway));
+            }
+            let (fwd, back) = get_lane_types(&tags(input.iter().map(|x| x.as_str()).collect()));
+            let actual = (lanes_to_string(&fwd), lanes_to_string(&back));
+            assert_eq!(
+                (expected_fwd.to_string(), expected_back.to_string()),
+                actual,
+                "oneway={:?}, parking:lane:{}",
+                oneway,
+                parking
+            );
+        }
+    }
+
+    #[test]
+    fn test_shoulder_sides() {
+        let check = |input: Vec<&str>, expected_fwd: &str, expected_back: &str| {
+            let mut osm_tags = tags(input.clone());
+            osm_tags.insert("highway".to_string(), "primary".to_string());
+            osm_tags.insert("sidewalk".to_string(), "none".to_string());
+            let (fwd, back) = get_lane_types(&osm_tags);
+            assert_eq!(
+                (expected_fwd.to_string(), expected_back.to_string()),
+                (lanes_to_string(&fwd), lanes_to_string(&back)),
+                "{:?}",
+                input
+            );
+        };
+        check(vec!["shoulder:right=yes"], "dh", "d");
+        check(vec!["shoulder:left=yes"], "d", "dh");
+        check(vec!["shoulder:right=yes", "oneway=-1"], "h", "d");
+        check(vec!["shoulder:right=yes", "oneway=yes"], "dh", "");
+        check(vec!["shoulder:both=yes", "oneway=yes"], "dh", "h");
+        check(vec!["shoulder=yes", "oneway=yes"], "dh", "");
+        // The sided keys win
+        check(vec!["shoulder=yes", "shoulder:left=no"], "dh", "d");
+        check(vec!["shoulder=right", "shoulder:left=yes"], "dh", "dh");
+    }
+
+    #[test]
+    fn test_sidewalk_sides() {
+        for (input, expected_fwd, expected_back) in vec![
+            (vec!["sidewalk=right"], "ds", "d"),
+            (vec!["sidewalk=left"], "d", "ds"),
+            (vec!["sidewalk=right", "oneway=-1"], "s", "d"),
+            (vec!["sidewalk=left", "oneway=-1"], "", "ds"),
+            (vec!["sidewalk=right", "oneway=yes"], "ds", ""),
+            (vec!["sidewalk:right=yes", "oneway=-1"], "s", "d"),
+            (vec!["sidewalk:left=separate"], "d", "ds"),
+            // The sided key wins
+            (vec!["sidewalk=both", "sidewalk:left=no"], "ds", "d"),
+            (vec!["sidewalk:both=yes", "oneway=-1"], "s", "ds"),
+        ] {
+            let mut osm_tags = tags(input.clone());
+            osm_tags.insert("highway".to_string(), "residential".to_string());
+            let (fwd, back) = get_lane_types(&osm_tags);
+            assert_eq!(
+                (expected_fwd.to_string(), expected_back.to_string()),
+                (lanes_to_string(&fwd), lanes_to_string(&back)),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_compare_to_osm() {
+        let osm = tags(vec![
+            "highway=residential",
+            "sidewalk=both",
+            "parking:lane:both=parallel",
+            "abst:synthetic_lanes=dbp/dp",
+        ]);
+        assert_eq!(
+            vec![
+                Discrepancy::MissingLanes {
+                    side: Side::Right,
+                    lt: LaneType::Sidewalk,
+                    count: 1,
+                },
+                Discrepancy::ExtraLanes {
+                    side: Side::Right,
+                    lt: LaneType::Biking,
+                    count: 1,
+                },
+                Discrepancy::MissingLanes {
+                    side: Side::Left,
+                    lt: LaneType::Sidewalk,
+                    count: 1,
+                },
+            ],
+            compare_to_osm(&RoadSpec::parse("dbp/dp".to_string()).unwrap(), &osm)
+        );
+
+        assert_eq!(
+            vec![Discrepancy::DifferentOrder { side: Side::Left }],
+            compare_to_osm(&RoadSpec::parse("dps/pds".to_string()).unwrap(), &osm)
+        );
+        assert!(compare_to_osm(&RoadSpec::parse("dps/dps".to_string()).unwrap(), &osm).is_empty());
+    }
+
+    #[test]
+    fn test_circular_junctions() {
+        for (value, kind) in &[
+            ("roundabout", CircularJunction::Roundabout),
+            ("circular", CircularJunction::Circular),
+        ] {
+            let osm_tags = tags(vec![
+                "highway=primary",
+                &format!("junction={}", value),
+                "lanes=2",
+            ]);
+            assert_eq!(Some(*kind), CircularJunction::parse(&osm_tags));
+            let (fwd, back) = get_lane_types(&osm_tags);
+            assert_lanes("ds", &fwd);
+            assert_lanes("", &back);
+        }
+        assert_eq!(
+            None,
+            CircularJunction::parse(&tags(vec!["highway=primary"]))
+        );
+    }
+
+    #[test]
+    fn test_motorroad() {
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec![
+                "highway=trunk",
+                "motorroad=yes",
+                "lanes=4",
+                "sidewalk=both",
+                "cycleway=lane",
+                "parking:lane:both=parallel",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("dd", &fwd);
+        assert_lanes("dd", &back);
+        assert_eq!(1, warnings.len());
+
+        let (fwd, _) = get_lane_types(&tags(vec![
+            "highway=trunk",
+            "motorroad=no",
+            "sidewalk=both",
+        ]));
+        assert_lanes("ds", &fwd);
+    }
+
+    #[test]
+    fn test_busway_lanes() {
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=primary",
+            "lanes=4",
+            "busway:both=lane",
+            "sidewalk=both",
+        ]));
+        assert_lanes("dus", &fwd);
+        assert_lanes("dus", &back);
+
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=primary",
+            "lanes=4",
+            "busway:right=lane",
+        ]));
+        assert_lanes("du", &fwd);
+        assert_lanes("dd", &back);
+
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=primary",
+            "oneway=yes",
+            "lanes=2",
+            "busway=lane",
+        ]));
+        assert_lanes("du", &fwd);
+        assert_lanes("", &back);
+
+        // Don't convert two lanes when both tagging schemes are used
+        let (fwd, _) = get_lane_types(&tags(vec![
+            "highway=primary",
+            "oneway=yes",
+            "lanes=3",
+            "bus:lanes=||designated",
+            "busway=lane",
+        ]));
+        assert_lanes("ddu", &fwd);
+    }
+
+    #[test]
+    fn test_areas() {
+        let osm_tags = tags(vec!["highway=pedestrian", "area=yes"]);
+        assert!(is_area(&osm_tags));
+        let (fwd, back) = get_lane_types(&osm_tags);
+        assert!(fwd.is_empty());
+        assert!(back.is_empty());
+
+        assert!(!is_area(&tags(vec!["highway=pedestrian", "area=no"])));
+    }
+
+    #[test]
+    fn test_advisory_speeds() {
+        let osm_tags = tags(vec![
+            "highway=motorway_link",
+            "maxspeed=65 mph",
+            "maxspeed:advisory=35 mph",
+        ]);
+        assert_eq!(
+            Some(Speed::miles_per_hour(35.0)),
+            advisory_speed_limit(&osm_tags)
+        );
+        assert_eq!(
+            None,
+            advisory_speed_limit(&tags(vec!["highway=motorway_link", "maxspeed=65 mph"]))
+        );
+        assert_eq!(
+            None,
+            advisory_speed_limit(&tags(vec!["maxspeed:advisory=slow"]))
+        );
+    }
+
+    #[test]
+    fn test_slip_roads() {
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec![
+                "highway=motorway_link",
+                "lanes=2",
+                "sidewalk=both",
+                "parking:lane:both=parallel",
+            ]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert_lanes("dd", &fwd);
+        assert!(back.is_empty());
+        assert_eq!(1, warnings.len());
+
+        // Not implicitly oneway, but still nobody walking along it
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=trunk_link", "sidewalk=right"]));
+        assert_lanes("d", &fwd);
+        assert_lanes("d", &back);
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=motorway_link", "oneway=no"]));
+        assert_lanes("d", &fwd);
+        assert_lanes("d", &back);
+
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=primary_link",
+            "junction=jughandle",
+            "sidewalk=right",
+            "parking:lane:right=parallel",
+        ]));
+        assert_lanes("ds", &fwd);
+        assert!(back.is_empty());
+
+        // Only the junction is tagged, which still shouldn't crash
+        let mut warnings = Vec::new();
+        let (fwd, back) = lane_types(
+            &tags(vec!["junction=jughandle", "parking:lane:both=parallel"]),
+            &LaneInferencePolicy::default(),
+            &mut warnings,
+        );
+        assert!(!fwd.contains(&LaneType::Parking));
+        assert!(!back.contains(&LaneType::Parking));
+        assert!(warnings
+            .iter()
+            .any(|x| x.starts_with("highway=? is a slip road")));
+
+        // Other links are unaffected
+        let (fwd, _) = get_lane_types(&tags(vec![
+            "highway=primary_link",
+            "oneway=yes",
+            "sidewalk=right",
+        ]));
+        assert_lanes("ds", &fwd);
+    }
+
+    #[test]
+    fn test_dead_ends() {
+        let osm_tags = tags(vec!["highway=residential", "noexit=yes", "sidewalk=both"]);
+        assert!(is_dead_end(&osm_tags));
+        let (fwd, back) = get_lane_types(&osm_tags);
+        assert_lanes("ds", &fwd);
+        assert_lanes("ds", &back);
+
+        assert!(!is_dead_end(&tags(vec!["highway=residential"])));
+    }
+
+    #[test]
+    fn test_vehicle_restrictions() {
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "vehicle=no",
+            "sidewalk=both",
+        ]));
+        assert_lanes("s", &fwd);
+        assert_lanes("s", &back);
+        // Bike, bus, and parking lanes go away too
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "vehicle=no",
+            "cycleway=lane",
+            "busway=lane",
+            "parking:lane:both=parallel",
+            "sidewalk=right",
+        ]));
+        assert_lanes("s", &fwd);
+        assert_lanes("", &back);
+        // There's always at least one lane
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "vehicle=no",
+            "sidewalk=none",
+        ]));
+        assert_lanes("s", &fwd);
+        assert_lanes("", &back);
+
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "motor_vehicle=no",
+            "cycleway=lane",
+            "parking:lane:both=parallel",
+            "sidewalk=both",
+        ]));
+        assert_lanes("bs", &fwd);
+        assert_lanes("bs", &back);
+        let (fwd, back) = get_lane_types(&tags(vec![
+            "highway=residential",
+            "motor_vehicle=no",
+            "sidewalk=right",
+        ]));
+        assert_lanes("bs", &fwd);
+        assert_lanes("b", &back);
+    }
+
+    #[test]
+    fn test_busway() {
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=busway"]));
+        assert_lanes("u", &fwd);
+        assert_lanes("u", &back);
+        let (fwd, back) = get_lane_types(&tags(vec!["highway=busway", "oneway=yes"]));
+        assert_lanes("u", &fwd);
+        assert_lanes("", &back);
+    }
+
+    #[test]
+    fn test_centerline_offset() {
+        // Wide forward, narrow back
+        let spec = RoadSpec::parse("ddps/s".to_string()).unwrap();
+        assert_eq!(SIDEWALK_THICKNESS, spec.centerline_offset());
+        assert_eq!(
+            NORMAL_LANE_THICKNESS * 3.0 + SIDEWALK_THICKNESS * 2.0,
+            spec.total_width()
+        );
+        assert!(spec.centerline_offset() < spec.total_width() / 2.0);
+
+        // The boundary is the near edge of the first fwd lane
+        let lanes = spec.split_at_width();
+        assert_eq!(
+            spec.centerline_offset() + NORMAL_LANE_THICKNESS / 2.0,
+            lanes[1].1
+        );
+
+        let symmetric = RoadSpec::parse("ds/ds".to_string()).unwrap();
+        assert_eq!(symmetric.total_width() / 2.0, symmetric.centerline_offset());
+        let oneway = RoadSpec::parse("dd/".to_string()).unwrap();
+        assert_eq!(Distance::ZERO, oneway.centerline_offset());
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = RoadSpecSchema::current();
+        assert_eq!(LaneType::all().len(), schema.lanes.len());
+        for lt in LaneType::all() {
+            let code = schema.lanes.iter().find(|x| x.lane_type == lt).unwrap();
+            assert_eq!(Some(lt), RoadSpec::char_to_lt(code.code));
+            assert_ne!(schema.separator, code.code);
+        }
+    }
+
+    #[test]
+    fn test_strip_cosmetic() {
+        for (input, expected) in &[
+            ("mdvbs/mdps", "dbs/dps"),
+            ("dvbvs/", "dbs/"),
+            // Nothing to strip
+            ("ldpsh/dps", "ldpsh/dps"),
+            ("/ucs", "/ucs"),
+        ] {
+            let spec = RoadSpec::parse(input.to_string()).unwrap();
+            let stripped = spec.strip_cosmetic();
+            assert_eq!(*expected, stripped.to_string());
+            assert!(spec.approx_eq_travel(&stripped));
+        }
+        assert_eq!(
+            vec![LaneType::Buffer, LaneType::Median],
+            LaneType::all()
+                .into_iter()
+                .filter(|lt| lt.is_cosmetic())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_estimated_capacity() {
+        let arterial = RoadSpec::parse("ddps/dds".to_string()).unwrap();
+        for side in &[Side::Right, Side::Left] {
+            assert_eq!(
+                3800.0,
+                arterial.estimated_capacity_vph(*side, Speed::miles_per_hour(50.0))
+            );
+            // Faster doesn't help
+            assert_eq!(
+                3800.0,
+                arterial.estimated_capacity_vph(*side, Speed::miles_per_hour(70.0))
+            );
+            let slow = arterial.estimated_capacity_vph(*side, Speed::miles_per_hour(25.0));
+            assert!((slow - 1900.0).abs() < 0.1);
+        }
+
+        let oneway = RoadSpec::oneway("dds").unwrap();
+        let speed = Speed::miles_per_hour(50.0);
+        assert_eq!(3800.0, oneway.estimated_capacity_vph(Side::Right, speed));
+        assert_eq!(0.0, oneway.estimated_capacity_vph(Side::Left, speed));
+    }
+
+    #[test]
+    fn test_requires_center_line() {
+        for (spec, expected) in &[
+            ("dds/", false),
+            ("/dps", false),
+            ("dps/ds", true),
+            ("ups/dps", true),
+            ("ldps/dps", false),
+            ("hdps/hdps", false),
+            ("ps/dps", false),
+            ("mdps/dps", false),
+            ("dps/mdps", false),
+        ] {
+            assert_eq!(
+                *expected,
+                RoadSpec::parse(spec.to_string())
+                    .unwrap()
+                    .requires_center_line(),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_right_of_way_classes() {
+        assert_eq!(RowClass::Narrow, right_of_way_class(Distance::ZERO));
+        assert_eq!(RowClass::Narrow, right_of_way_class(Distance::meters(9.99)));
+        assert_eq!(RowClass::Medium, right_of_way_class(Distance::meters(10.0)));
+        assert_eq!(
+            RowClass::Medium,
+            right_of_way_class(Distance::meters(19.99))
+        );
+        assert_eq!(RowClass::Wide, right_of_way_class(Distance::meters(20.0)));
+
+        // 2.5m driving lanes and 1.5m sidewalks
+        for (spec, expected) in &[
+            ("ds/ds", RowClass::Narrow),
+            ("dps/dps", RowClass::Medium),
+            ("ldddps/dddps", RowClass::Wide),
+        ] {
+            assert_eq!(
+                *expected,
+                RoadSpec::parse(spec.to_string())
+                    .unwrap()
+                    .right_of_way_class(),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_width_profile() {
+        let close = |a: Distance, b: Distance| (a - b).abs() < Distance::meters(0.01);
+        let spec = RoadSpec::parse("dps/ds".to_string()).unwrap();
+        let sum = |profile: &Vec<(LaneType, Distance)>| profile.iter().map(|(_, w)| *w).sum();
+
+        // Exactly fits
+        let profile = spec.width_profile(spec.total_width());
+        assert_eq!(
+            spec.back
+                .iter()
+                .rev()
+                .chain(spec.fwd.iter())
+                .map(|lt| (*lt, default_lane_width(*lt)))
+                .collect::<Vec<_>>(),
+            profile
+        );
+
+        // Too narrow, so everything shrinks
+        let profile = spec.width_profile(spec.total_width() / 2.0);
+        assert!(close(spec.total_width() / 2.0, sum(&profile)));
+        for (lt, width) in &profile {
+            assert!(close(default_lane_width(*lt) / 2.0, *width));
+        }
+
+        // Extra space only goes to the driving lanes
+        let profile = spec.width_profile(spec.total_width() + Distance::meters(2.0));
+        assert!(close(
+            spec.total_width() + Distance::meters(2.0),
+            sum(&profile)
+        ));
+        for (lt, width) in &profile {
+            if *lt == LaneType::Driving {
+                assert!(close(
+                    default_lane_width(*lt) + Distance::meters(1.0),
+                    *width
+                ));
+            } else {
+                assert_eq!(default_lane_width(*lt), *width);
+            }
+        }
+
+        // Nothing can absorb the space, so it's spread out
+        let spec = RoadSpec::oneway("s").unwrap();
+        let profile = spec.width_profile(Distance::meters(4.0));
+        assert!(close(Distance::meters(4.0), profile[0].1));
+    }
+
+    #[test]
+    fn test_tagged_width_profile() {
+        let close = |a: Distance, b: Distance| (a - b).abs() < Distance::meters(0.01);
+        let spec = RoadSpec::parse("dps/ds".to_string()).unwrap();
+        let width_of = |profile: &Vec<(LaneType, Distance)>, lt: LaneType| -> Distance {
+            profile
+                .iter()
+                .filter(|(x, _)| *x == lt)
+                .map(|(_, w)| *w)
+                .sum()
+        };
+
+        assert_eq!(
+            None,
+            spec.tagged_width_profile(&RoadWidths::parse(&tags(vec!["highway=primary"])))
+        );
+
+        // Just the total means the same as width_profile
+        let widths = RoadWidths::parse(&tags(vec!["highway=primary", "width=20"]));
+        assert_eq!(None, widths.carriageway);
+        assert_eq!(
+            Some(spec.width_profile(Distance::meters(20.0))),
+            spec.tagged_width_profile(&widths)
+        );
+
+        // 9m of driving lanes, and the remaining 11m split between the sidewalks and parking
+        let widths = RoadWidths::parse(&tags(vec![
+            "highway=primary",
+            "width=20",
+            "width:carriageway=9",
+        ]));
+        assert_eq!(Some(Distance::meters(20.0)), widths.total);
+        assert_eq!(Some(Distance::meters(9.0)), widths.carriageway);
+        let profile = spec.tagged_width_profile(&widths).unwrap();
+        assert!(close(
+            Distance::meters(9.0),
+            width_of(&profile, LaneType::Driving)
+        ));
+        let rest =
+            default_lane_width(LaneType::Sidewalk) * 2.0 + default_lane_width(LaneType::Parking);
+        for lt in &[LaneType::Sidewalk, LaneType::Parking] {
+            for (x, width) in &profile {
+                if x == lt {
+                    assert!(close(
+                        Distance::meters(11.0) * (default_lane_width(*lt) / rest),
+                        *width
+                    ));
+                }
+            }
+        }
+        // Spreading the whole width over everything would give the driving lanes much more room
+        assert!(
+            width_of(
+                &spec.width_profile(Distance::meters(20.0)),
+                LaneType::Driving
+            ) > Distance::meters(9.0)
+        );
+
+        // Only the carriageway is known, so the other lanes keep their defaults
+        let widths = RoadWidths::parse(&tags(vec!["highway=primary", "width:carriageway=9"]));
+        let profile = spec.tagged_width_profile(&widths).unwrap();
+        assert!(close(
+            Distance::meters(9.0),
+            width_of(&profile, LaneType::Driving)
+        ));
+        assert_eq!(
+            default_lane_width(LaneType::Parking),
+            width_of(&profile, LaneType::Parking)
+        );
+    }
+
+    #[test]
+    fn test_pedestrian_crossing_points() {
+        let spec = RoadSpec::parse("dps/ds".to_string()).unwrap();
+        assert_eq!(
+            vec![CrossingCandidate {
+                back_sidewalk: 1,
+                fwd_sidewalk: 2,
+                length: default_lane_width(LaneType::Sidewalk)
+                    + default_lane_width(LaneType::Driving) * 3.0,
+            }],
+            spec.pedestrian_crossing_points()
+        );
+
+        assert!(RoadSpec::parse("dps/d".to_string())
+            .unwrap()
+            .pedestrian_crossing_points()
+            .is_empty());
+        assert!(RoadSpec::oneway("ds")
+            .unwrap()
+            .pedestrian_crossing_points()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_split_at_width() {
+        let spec = RoadSpec::parse("dps/ds".to_string()).unwrap();
+        let lanes = spec.split_at_width();
+        assert_eq!(
+            vec![
+                LaneType::Sidewalk,
+                LaneType::Driving,
+                LaneType::Driving,
+                LaneType::Parking,
+                LaneType::Sidewalk,
+            ],
+            lanes.iter().map(|(lt, _)| *lt).collect::<Vec<_>>()
+        );
+        assert_eq!(SIDEWALK_THICKNESS / 2.0, lanes[0].1);
+        // Each center is half of this lane and half of the previous lane past the last one.
+        for pair in lanes.windows(2) {
+            assert_eq!(
+                pair[0].1 + (default_lane_width(pair[0].0) + default_lane_width(pair[1].0)) / 2.0,
+                pair[1].1
+            );
+        }
+        let (lt, last) = lanes.last().unwrap();
+        assert_eq!(
+            SIDEWALK_THICKNESS * 2.0 + NORMAL_LANE_THICKNESS * 3.0,
+            *last + default_lane_width(*lt) / 2.0
+        );
+    }
+
+    #[test]
+    fn test_malformed_lane_counts() {
+        let mut warnings = Vec::new();
+        assert_eq!(
+            (vec![LaneType::Driving; 2], Vec::new()),
+            lane_types(
+                &tags(vec!["highway=primary", "oneway=yes", "lanes=1.5"]),
+                &LaneInferencePolicy::default(),
+                &mut warnings
+            )
+        );
+        assert_eq!(1, warnings.len());
+
+        let mut warnings = Vec::new();
+        assert_eq!(
+            (vec![LaneType::Driving; 3], Vec::new()),
+            lane_types(
+                &tags(vec!["highway=primary", "oneway=yes", "lanes=2;3"]),
+                &LaneInferencePolicy::default(),
+                &mut warnings
+            )
+        );
+        assert_eq!(1, warnings.len());
+
+        // Totally unparseable counts still fall back to the defaults, but not silently.
+        let mut warnings = Vec::new();
+        assert_eq!(
+            (vec![LaneType::Driving], vec![LaneType::Driving]),
+            lane_types(
+                &tags(vec!["highway=primary", "lanes=lots"]),
+                &LaneInferencePolicy::default(),
+                &mut warnings
+            )
+        );
+        assert_eq!(1, warnings.len());
+    }
+
+    #[test]
+    fn test_lane_at_cross_section_index() {
+        let spec = RoadSpec::parse("dbs/dps".to_string()).unwrap();
+        assert_eq!(
+            Some((Side::Left, 2, LaneType::Sidewalk)),
+            spec.lane_at_cross_section_index(0)
+        );
+        assert_eq!(
+            Some((Side::Left, 0, LaneType::Driving)),
+            spec.lane_at_cross_section_index(2)
+        );
+        assert_eq!(
+            Some((Side::Right, 0, LaneType::Driving)),
+            spec.lane_at_cross_section_index(3)
+        );
+        assert_eq!(
+            Some((Side::Right, 2, LaneType::Sidewalk)),
+            spec.lane_at_cross_section_index(5)
+        );
+        assert_eq!(None, spec.lane_at_cross_section_index(6));
+
+        let oneway = RoadSpec::parse("ds/".to_string()).unwrap();
+        assert_eq!(
+            Some((Side::Right, 0, LaneType::Driving)),
+            oneway.lane_at_cross_section_index(0)
+        );
+        assert_eq!(None, oneway.lane_at_cross_section_index(2));
+    }
+
+    #[test]
+    fn test_osm_lane_numbering_by_side() {
+        let spec = RoadSpec::parse("lubps/dds".to_string()).unwrap();
+        // The center turn lane and bike lane don't count.
+        assert_eq!(vec![1], osm_lane_numbering(&spec.fwd));
+        assert_eq!(vec![0, 1], osm_lane_numbering(&spec.back));
+        assert_eq!(
+            vec![
+                (Side::Left, 1),
+                (Side::Left, 0),
+                (Side::Right, 0),
+                (Side::Right, 1)
+            ],
+            spec.osm_lane_numbering()
+        );
+
+        let oneway = RoadSpec::parse("ddds/".to_string()).unwrap();
+        assert_eq!(
+            vec![(Side::Right, 0), (Side::Right, 1), (Side::Right, 2)],
+            oneway.osm_lane_numbering()
+        );
+        let reversed = RoadSpec::parse("/bdd".to_string()).unwrap();
+        assert_eq!(
+            vec![(Side::Left, 1), (Side::Left, 2)],
+            reversed.osm_lane_numbering()
+        );
+    }
+
+    #[test]
+    fn test_driving_lane_indices() {
+        let spec = RoadSpec::parse("ldbdps/dbps".to_string()).unwrap();
+        assert_eq!(vec![1, 3], spec.driving_lane_indices(Side::Right));
+        assert_eq!(vec![0], spec.driving_lane_indices(Side::Left));
+
+        let oneway = RoadSpec::parse("bps/".to_string()).unwrap();
+        assert!(oneway.driving_lane_indices(Side::Right).is_empty());
+        assert!(oneway.driving_lane_indices(Side::Left).is_empty());
+    }
+
+    #[test]
+    fn test_oneway_spec() {
+        let spec = RoadSpec::oneway("dds").unwrap();
+        assert_lanes("dds", &spec.fwd);
+        assert!(spec.back.is_empty());
+        assert_eq!("dds/", spec.to_string());
+        assert_eq!(
+            "dds/",
+            RoadSpec::parse("dds/".to_string()).unwrap().to_string()
+        );
+
+        assert!(RoadSpec::oneway("dxs").is_none());
+        assert!(RoadSpec::oneway("dd/s").is_none());
+        assert!(RoadSpec::oneway("").is_none());
+    }
+
+    #[test]
+    fn test_swap_sides_preserving_curb_features() {
+        let mut spec = RoadSpec::parse("ddbps/ds".to_string()).unwrap();
+        spec.swap_sides_preserving_curb_features();
+        assert_eq!("dps/ddbs", spec.to_string());
+        spec.swap_sides_preserving_curb_features();
+        assert_eq!("ddbps/ds", spec.to_string());
+
+        let mut spec = RoadSpec::parse("ldps/ddp".to_string()).unwrap();
+        spec.swap_sides_preserving_curb_features();
+        assert_eq!("lddps/dp", spec.to_string());
+
+        // Compare to mirroring the whole road
+        let mut spec = RoadSpec::parse("dp/d".to_string()).unwrap();
+        std::mem::swap(&mut spec.fwd, &mut spec.back);
+        assert_eq!("d/dp", spec.to_string());
+        let mut spec = RoadSpec::parse("dp/d".to_string()).unwrap();
+        spec.swap_sides_preserving_curb_features();
+        assert_eq!("dp/d", spec.to_string());
+    }
+
+    #[test]
+    fn test_spec_locales() {
+        let german = SpecLocale::from_code("de").unwrap();
+        assert_eq!(Some("dpbs/ds".to_string()), german.to_canonical("fprg/fg"));
+        assert_eq!(
+            Some("fprg/fg".to_string()),
+            german.from_canonical("dpbs/ds")
+        );
+        assert_eq!(None, german.to_canonical("dd/"));
+        assert_eq!(None, german.from_canonical("ff/"));
+
+        // Every locale's table must be reversible
+        for locale in &[SpecLocale::English, SpecLocale::German] {
+            let all: String = LaneType::all()
+                .into_iter()
+                .map(RoadSpec::lt_to_char)
+                .collect();
+            let localized = locale.from_canonical(&all).unwrap();
+            assert_eq!(Some(all), locale.to_canonical(&localized));
+        }
+    }
+
+    #[test]
+    fn test_to_ascii_diagram() {
+        let diagram = |x: &str| RoadSpec::parse(x.to_string()).unwrap().to_ascii_diagram();
+        assert_eq!("|S|P|D D|=|D D|P|S|", diagram("ddps/ddps"));
+        assert_eq!("|S|D|=|L|D|B|S|", diagram("ldbs/ds"));
+        assert_eq!("|D D|S|", diagram("dds/"));
+        assert_eq!("|S|D|", diagram("/ds"));
+    }
+
+    #[test]
+    fn test_is_valid_for_class() {
+        let spec = |x: &str| RoadSpec::parse(x.to_string()).unwrap();
+        assert!(spec("dd/dd")
+            .is_valid_for_class(RoadClass::Motorway)
+            .is_ok());
+        assert_eq!(
+            Err("Motorway roads can't have a parking lane".to_string()),
+            spec("ddp/dd").is_valid_for_class(RoadClass::Motorway)
+        );
+        assert!(spec("dds/dds")
+            .is_valid_for_class(RoadClass::Motorway)
+            .is_err());
+        assert!(spec("dps/dps")
+            .is_valid_for_class(RoadClass::Residential)
+            .is_ok());
+        assert!(spec("bs/bs")
+            .is_valid_for_class(RoadClass::Residential)
+            .is_err());
+    }
+
+    #[test]
+    fn test_describe_changes() {
+        let spec = |x: &str| RoadSpec::parse(x.to_string()).unwrap();
+        assert_eq!(
+            vec![
+                "Added a bike lane on the right",
+                "Removed a parking lane on the left"
+            ],
+            spec("dbps/ds").describe_changes_from(&spec("dps/dps"))
+        );
+        assert_eq!(
+            vec!["Removed 2 driving lanes on the right"],
+            spec("ds/dds").describe_changes_from(&spec("ddds/dds"))
+        );
+        assert_eq!(
+            vec!["Rearranged the lanes on the left"],
+            spec("ds/bds").describe_changes_from(&spec("ds/dbs"))
+        );
+        assert!(spec("ds/ds")
+            .describe_changes_from(&spec("ds/ds"))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_merge_adjacent_driving() {
+        let check = |input: &str, expected: &str, changed: bool| {
+            let mut spec = RoadSpec::parse(input.to_string()).unwrap();
+            assert_eq!(changed, spec.merge_adjacent_driving());
+            assert_eq!(expected, spec.to_string());
+        };
+        check("dpd/", "ddp/", true);
+        check("ldpdbs/dpdps", "lddpbs/ddpps", true);
+        check("ddps/dps", "ddps/dps", false);
+        check("dbd/", "dbd/", false);
+        check("ps/ps", "ps/ps", false);
+    }
+
+    #[test]
+    fn test_ensure_min_driving_lane() {
+        let check = |input: &str, expected: &str, changed: bool| {
+            let mut spec = RoadSpec::parse(input.to_string()).unwrap();
+            assert_eq!(changed, spec.ensure_min_driving_lane());
+            assert_eq!(expected, spec.to_string());
+        };
+        check("pp/pp", "dpp/pp", true);
+        check("/pps", "/dpps", true);
+        check("lps/ps", "ldps/ps", true);
+        check("dps/ps", "dps/ps", false);
+        check("ps/dps", "ps/dps", false);
+        // Buses and construction count as vehicle lanes
+        check("ups/ps", "ups/ps", false);
+        check("c/c", "c/c", false);
+        // Nothing here needs a driving lane
+        check("s/s", "s/s", false);
+        check("u/u", "u/u", false);
+        check("bs/bs", "bs/bs", false);
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let mut warnings = Vec::new();
+        assert!(RoadSpec::parse("dzps/d?s".to_string()).is_none());
+        assert_eq!(
+            "dps/ds",
+            RoadSpec::parse_skipping("dzps/d?s", &mut warnings)
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(2, warnings.len());
+
+        // Agrees with the strict version when nothing's wrong
+        let mut warnings = Vec::new();
+        assert_eq!(
+            RoadSpec::parse("ldps/ds".to_string()).unwrap().to_string(),
+            RoadSpec::parse_skipping("ldps/ds", &mut warnings)
+                .unwrap()
+                .to_string()
+        );
+        assert!(warnings.is_empty());
+
+        // Nothing recognizable left
+        let mut warnings = Vec::new();
+        assert!(RoadSpec::parse_skipping("zz/", &mut warnings).is_none());
+        assert!(RoadSpec::parse_skipping("dps", &mut warnings).is_none());
+        assert!(RoadSpec::parse_lenient("dps/ds".to_string())
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_lanes_iter() {
+        let spec = RoadSpec::parse("ldps/bs".to_string()).unwrap();
+        assert_eq!(
+            vec![
+                (Side::Right, 0, LaneType::SharedLeftTurn),
+                (Side::Right, 1, LaneType::Driving),
+                (Side::Right, 2, LaneType::Parking),
+                (Side::Right, 3, LaneType::Sidewalk),
+                (Side::Left, 0, LaneType::Biking),
+                (Side::Left, 1, LaneType::Sidewalk),
+            ],
+            spec.lanes_iter().collect::<Vec<_>>()
+        );
+        for s in &["ldps/bs", "dd/", "/ds"] {
+            assert_eq!(*s, RoadSpec::parse(s.to_string()).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_num_lanes() {
+        let spec = RoadSpec::parse("ldbdps/dps".to_string()).unwrap();
+        assert_eq!(6, spec.num_lanes(Side::Right));
+        assert_eq!(3, spec.num_lanes(Side::Left));
+        assert_eq!(4, spec.total_travel_lanes());
+        let oneway = RoadSpec::parse("ds/".to_string()).unwrap();
+        assert_eq!(2, oneway.num_lanes(Side::Right));
+        assert_eq!(0, oneway.num_lanes(Side::Left));
+    }
+
+    #[test]
+    fn test_spec_has_sidewalk() {
+        for (spec, right, left) in &[
+            ("dds/", true, false),
+            ("dd/s", false, true),
+            ("dps/dps", true, true),
+            ("dp/d", false, false),
+        ] {
+            let spec = RoadSpec::parse(spec.to_string()).unwrap();
+            assert_eq!(*right, spec.has_sidewalk(Side::Right));
+            assert_eq!(*left, spec.has_sidewalk(Side::Left));
+        }
+    }
+
+    #[test]
+    fn test_append_side() {
+        let mut spec = RoadSpec::parse("dd/d".to_string()).unwrap();
+        spec.append_side(Side::Right, &[LaneType::Parking, LaneType::Sidewalk]);
+        assert_eq!("ddps/d", spec.to_string());
+        spec.append_side(Side::Left, &[LaneType::Biking]);
+        assert_eq!("ddps/db", spec.to_string());
+        spec.append_side(Side::Left, &[]);
+        assert_eq!("ddps/db", spec.to_string());
+
+        let mut oneway = RoadSpec::parse("d/".to_string()).unwrap();
+        oneway.append_side(Side::Left, &[LaneType::Sidewalk]);
+        assert_eq!("d/s", oneway.to_string());
+    }
+
+    // Each side is ordered from the centerline out to the curb. A center turn lane can only be in
+    // the middle, nothing can be outside a sidewalk, and no driving lane can be outside parking.
+    fn check_ordering(side: &[LaneType]) -> Result<(), String> {
+        let spec = RoadSpec {
+            fwd: side.to_vec(),
+            back: Vec::new(),
+        };
+        for (idx, lt) in side.iter().enumerate() {
+            let outside = &side[idx + 1..];
+            if *lt == LaneType::SharedLeftTurn && idx != 0 {
+                return Err(format!("{} has a turn lane away from the center", spec));
+            }
+            if *lt == LaneType::Sidewalk && !outside.is_empty() {
+                return Err(format!("{} has lanes outside a sidewalk", spec));
+            }
+            if *lt == LaneType::Parking && outside.contains(&LaneType::Driving) {
+                return Err(format!("{} has a driving lane outside parking", spec));
+            }
+        }
+        Ok(())
+    }
+
+    fn arbitrary_tags() -> impl Strategy<Value = BTreeMap<String, String>> {
+        fn maybe(values: Vec<&'static str>) -> impl Strategy<Value = Option<&'static str>> {
+            proptest::option::of(proptest::sample::select(values))
+        }
+        let counts = vec!["0", "1", "2", "3", "4"];
+
+        let road = (
+            proptest::sample::select(vec![
+                "residential",
+                "primary",
+                "secondary",
+                "service",
+                "motorway",
+                "construction",
+                "footway",
+            ]),
+            maybe(vec!["yes", "no", "reversible", "-1"]),
+            maybe(counts.clone()),
+            maybe(counts.clone()),
+            maybe(counts),
+            maybe(vec!["1"]),
+        );
+        let extras = (
+            maybe(vec!["lane", "track", "no"]),
+            maybe(vec!["lane"]),
+            maybe(vec!["lane"]),
+            maybe(vec!["parallel", "diagonal", "no_parking"]),
+            maybe(vec!["both", "left", "right", "none", "separate"]),
+            maybe(vec!["yes"]),
+            maybe(vec!["yes", "left", "right", "no"]),
+        );
+        (road, extras).prop_map(
+            |(
+                (highway, oneway, lanes, lanes_fwd, lanes_back, both_ways),
+                (cycleway, cycleway_left, cycleway_right, parking, sidewalk, bus_lanes, shoulder),
+            )| {
+                let mut tags = BTreeMap::new();
+                tags.insert("highway".to_string(), highway.to_string());
+                for (k, v) in vec![
+                    ("oneway", oneway),
+                    ("lanes", lanes),
+                    ("lanes:forward", lanes_fwd),
+                    ("lanes:backward", lanes_back),
+                    ("lanes:both_ways", both_ways),
+                    ("cycleway", cycleway),
+                    ("cycleway:left", cycleway_left),
+                    ("cycleway:right", cycleway_right),
+                    ("parking:lane:both", parking),
+                    ("sidewalk", sidewalk),
+                    ("bus:lanes", bus_lanes),
+                    ("shoulder", shoulder),
+                ] {
+                    if let Some(v) = v {
+                        tags.insert(k.to_string(), v.to_string());
+                    }
+                }
+                tags
+            },
+        )
+    }
+
+    // Only keep the consumed tags, plus something unrelated so that both versions count as tagged.
+    fn only_consumed(osm_tags: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+        let mut result: BTreeMap<String, String> = osm_tags
+            .iter()
+            .filter(|(k, _)| consumed_tags().contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        result.insert("note".to_string(), "unrelated".to_string());
+        result
+    }
+
+    #[test]
+    fn test_parsed_tags() {
+        for kv in &[
+            vec!["highway=residential"],
+            vec!["highway=motorway_link"],
+            vec!["highway=primary_link", "oneway=-1", "cycleway=lane"],
+            vec!["highway=primary", "oneway=yes", "lanes:backward=1"],
+            vec!["highway=residential", "parking:lane:both=parallel"],
+            vec!["highway=residential", "parking:lane:left=diagonal"],
+            vec!["parking:lane:right=no_parking", "parking:lane:both=marked"],
+        ] {
+            let osm_tags = tags(kv.clone());
+            let parsed = ParsedTags::new(&osm_tags);
+            assert_eq!(
+                osm_tags.get("highway").map(|x| x.as_str()),
+                parsed.highway,
+                "{:?}",
+                kv
+            );
+            assert_eq!(
+                parsed.highway.and_then(RoadClass::from_highway),
+                parsed.class
+            );
+            assert_eq!(is_oneway(&osm_tags), parsed.oneway, "{:?}", kv);
+            assert_eq!(is_reversed_oneway(&osm_tags), parsed.reversed, "{:?}", kv);
+            assert_eq!(
+                osm_tags.get("cycleway").map(|x| x.as_str()),
+                parsed.cycleway
+            );
+            for (side, key) in &[
+                (Side::Left, "parking:lane:left"),
+                (Side::Right, "parking:lane:right"),
+            ] {
+                assert_eq!(
+                    has_parking(osm_tags.get(*key))
+                        || has_parking(osm_tags.get("parking:lane:both")),
+                    parsed.parking(*side),
+                    "{:?}",
+                    kv
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch() {
+        let all_tags: Vec<BTreeMap<String, String>> = vec![
+            vec!["highway=residential"],
+            vec!["highway=primary", "lanes=3", "oneway=yes", "sidewalk=right"],
+            vec!["highway=secondary", "lanes=five", "cycleway=lane"],
+            vec!["highway=footway"],
+            vec![
+                "highway=tertiary",
+                "oneway=-1",
+                "parking:lane:left=parallel",
+            ],
+        ]
+        .into_iter()
+        .map(tags)
+        .collect();
+        let batch = get_lane_types_batch(&all_tags).unwrap();
+        assert_eq!(all_tags.len(), batch.len());
+        for (osm_tags, (fwd, back)) in all_tags.iter().zip(batch) {
+            let (expected_fwd, expected_back) = get_lane_types(osm_tags);
+            assert_eq!(expected_fwd, fwd);
+            assert_eq!(expected_back, back);
+        }
+        assert!(get_lane_types_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_consumed_tags() {
+        // Cover every branch of lane_types, along with some keys that shouldn't matter.
+        for kv in vec![
+            vec!["abst:synthetic_lanes=dps/ds"],
+            vec!["highway=primary", "junction=roundabout"],
+            vec!["highway=pedestrian", "area=yes"],
+            vec!["highway=footway", "surface=asphalt"],
+            vec![
+                "highway=path",
+                "foot=designated",
+                "bicycle=designated",
+                "segregated=yes",
+            ],
+            vec!["highway=busway", "oneway=yes"],
+            vec!["highway=cycleway", "oneway:bicycle=-1"],
+            vec!["highway=footway", "oneway:foot=-1"],
+            vec!["highway=primary", "shoulder:right=yes", "shoulder:both=no"],
+            vec![
+                "highway=residential",
+                "sidewalk:left=yes",
+                "sidewalk:both=no",
+            ],
+            vec!["highway=motorway_link", "sidewalk=both"],
+            vec!["highway=primary", "junction=jughandle", "oneway=-1"],
+            vec!["highway=residential", "lane_markings=no", "oneway=yes"],
+            vec!["highway=cycleway", "cycleway=crossing", "oneway=-1"],
+            vec!["highway=primary", "oneway=yes", "lanes:backward=1"],
+            vec![
+                "highway=primary",
+                "lanes=5",
+                "lanes:forward=2",
+                "lanes:both_ways=1",
+                "maxspeed=30 mph",
+            ],
+            vec!["highway=primary", "access=no", "bus=yes"],
+            vec!["highway=construction"],
+            vec![
+                "highway=secondary",
+                "centre_turn_lane=yes",
+                "bus:lanes=|designated",
+            ],
+            vec![
+                "highway=residential",
+                "cycleway:left=lane",
+                "cycleway:right=lane",
+            ],
+            vec!["highway=residential", "oneway=-1", "cycleway=lane"],
+            vec![
+                "highway=secondary",
+                "cycleway=track",
+                "cycleway:placement=middle",
+            ],
+            vec!["highway=primary", "bicycle=use_sidepath", "cycleway=lane"],
+            vec![
+                "highway=residential",
+                "parking:lane:left=parallel",
+                "parking:lane:right=diagonal",
+                "parking:lane:both=no_parking",
+            ],
+            vec!["highway=trunk", "shoulder=right", "sidewalk=left"],
+            vec!["highway=primary", "lanes=4", "busway:both=lane"],
+            vec!["highway=primary", "busway=lane", "busway:left=no"],
+            vec!["highway=primary", "busway:left=lane", "busway:right=lane"],
+            vec!["highway=trunk", "motorroad=yes", "sidewalk=both"],
+            vec!["highway=residential", "vehicle=no", "sidewalk=both"],
+            vec![
+                "highway=residential",
+                "motor_vehicle=no",
+                "parking:lane:both=parallel",
+            ],
+        ] {
+            let osm_tags = tags(kv);
+            let mut with_note = osm_tags.clone();
+            with_note.insert("note".to_string(), "unrelated".to_string());
+            assert_eq!(
+                get_lane_types(&with_note),
+                get_lane_types(&only_consumed(&osm_tags)),
+                "consumed_tags is missing something used by {:?}",
+                osm_tags
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_lane_ordering(tags in arbitrary_tags()) {
+            let (fwd, back) = get_lane_types(&tags);
+            let result = check_ordering(&fwd).and_then(|_| check_ordering(&back));
+            prop_assert!(result.is_ok(), "{:?} produced a bad ordering: {:?}", tags, result);
+        }
+
+        #[test]
+        fn test_only_consumed_tags_matter(tags in arbitrary_tags()) {
+            let mut with_note = tags.clone();
+            with_note.insert("note".to_string(), "unrelated".to_string());
+            prop_assert_eq!(get_lane_types(&with_note), get_lane_types(&only_consumed(&tags)));
+        }
+    }
+}
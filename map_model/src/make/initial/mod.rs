@@ -1,10 +1,13 @@
 mod geometry;
+pub mod lane_details;
 pub mod lane_specs;
+pub mod turn_lanes;
 
 pub use self::geometry::intersection_polygon;
+use self::lane_details::LaneDetails;
 use crate::raw::{OriginalIntersection, OriginalRoad, RawMap, RawRoad};
-use crate::{IntersectionType, LaneType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
-use abstutil::Timer;
+use crate::{IntersectionType, LaneType};
+use abstutil::{Timer, Warn};
 use geom::{Bounds, Distance, PolyLine, Pt2D};
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -28,16 +31,13 @@ pub struct Road {
 }
 
 impl Road {
-    pub fn new(id: OriginalRoad, r: &RawRoad) -> Road {
-        let lane_specs = get_lane_specs(&r.osm_tags);
+    pub fn new(id: OriginalRoad, r: &RawRoad, timer: &mut Timer) -> Road {
+        let lane_specs =
+            get_lane_specs(&r.osm_tags).with_context(timer, format!("lanes for {}", id));
         let mut fwd_width = Distance::ZERO;
         let mut back_width = Distance::ZERO;
         for l in &lane_specs {
-            let w = if l.lane_type == LaneType::Sidewalk {
-                SIDEWALK_THICKNESS
-            } else {
-                NORMAL_LANE_THICKNESS
-            };
+            let w = lane_specs::default_lane_width(l.lane_type);
             if l.reverse_pts {
                 back_width += w;
             } else {
@@ -94,10 +94,15 @@ impl InitialMap {
                 timer.warn(format!("Skipping loop {}", id));
                 continue;
             }
+            // Older RawMaps (and map_editor) might still have these. They've got no lanes.
+            if lane_specs::is_area(&r.osm_tags) {
+                timer.warn(format!("Skipping area {}", id));
+                continue;
+            }
             m.intersections.get_mut(&id.i1).unwrap().roads.insert(*id);
             m.intersections.get_mut(&id.i2).unwrap().roads.insert(*id);
 
-            m.roads.insert(*id, Road::new(*id, r));
+            m.roads.insert(*id, Road::new(*id, r, timer));
         }
 
         timer.start_iter("find each intersection polygon", m.intersections.len());
@@ -114,26 +119,29 @@ impl InitialMap {
 pub struct LaneSpec {
     pub lane_type: LaneType,
     pub reverse_pts: bool,
+    pub details: LaneDetails,
 }
 
-pub fn get_lane_specs(osm_tags: &BTreeMap<String, String>) -> Vec<LaneSpec> {
-    let (side1_types, side2_types) = lane_specs::get_lane_types(osm_tags);
-
-    let mut specs: Vec<LaneSpec> = Vec::new();
-    for lane_type in side1_types {
-        specs.push(LaneSpec {
-            lane_type,
-            reverse_pts: false,
-        });
-    }
-    for lane_type in side2_types {
-        specs.push(LaneSpec {
-            lane_type,
-            reverse_pts: true,
-        });
-    }
-    if specs.is_empty() {
-        panic!("Road with tags {:?} wound up with no lanes!", osm_tags);
-    }
-    specs
+pub fn get_lane_specs(osm_tags: &BTreeMap<String, String>) -> Warn<Vec<LaneSpec>> {
+    lane_details::get_lane_details_warn(osm_tags).map(|(side1, side2)| {
+        let mut specs: Vec<LaneSpec> = Vec::new();
+        for details in side1 {
+            specs.push(LaneSpec {
+                lane_type: details.lt,
+                reverse_pts: false,
+                details,
+            });
+        }
+        for details in side2 {
+            specs.push(LaneSpec {
+                lane_type: details.lt,
+                reverse_pts: true,
+                details,
+            });
+        }
+        if specs.is_empty() {
+            panic!("Road with tags {:?} wound up with no lanes!", osm_tags);
+        }
+        specs
+    })
 }
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+
+// https://wiki.openstreetmap.org/wiki/Key:turn
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TurnIndication {
+    // Unmarked; any movement is allowed
+    None,
+    Left,
+    SlightLeft,
+    SharpLeft,
+    Through,
+    Right,
+    SlightRight,
+    SharpRight,
+    Reverse,
+    MergeToLeft,
+    MergeToRight,
+}
+
+impl TurnIndication {
+    fn parse(value: &str) -> Option<TurnIndication> {
+        match value {
+            "" | "none" => Some(TurnIndication::None),
+            "left" => Some(TurnIndication::Left),
+            "slight_left" => Some(TurnIndication::SlightLeft),
+            "sharp_left" => Some(TurnIndication::SharpLeft),
+            "through" => Some(TurnIndication::Through),
+            "right" => Some(TurnIndication::Right),
+            "slight_right" => Some(TurnIndication::SlightRight),
+            "sharp_right" => Some(TurnIndication::SharpRight),
+            "reverse" => Some(TurnIndication::Reverse),
+            "merge_to_left" => Some(TurnIndication::MergeToLeft),
+            "merge_to_right" => Some(TurnIndication::MergeToRight),
+            _ => None,
+        }
+    }
+}
+
+// Parses something like "left|through;right||". There's one entry per lane, ordered left to right
+// in the direction of travel, and each lane can have several indications. An empty entry means the
+// lane is unmarked. Returns None if anything isn't recognized.
+pub fn parse_turn_lanes(value: &str) -> Option<Vec<Vec<TurnIndication>>> {
+    let mut lanes = Vec::new();
+    for lane in value.split('|') {
+        let mut turns = Vec::new();
+        for turn in lane.split(';') {
+            turns.push(TurnIndication::parse(turn.trim())?);
+        }
+        lanes.push(turns);
+    }
+    Some(lanes)
+}
+
+// Makes the output of parse_turn_lanes fit num_lanes, for when the tag disagrees with the lane
+// count. Extra entries on the right are dropped, and missing ones are filled in as unmarked. The
+// second value describes the repair, if one was needed.
+pub fn repair_turn_lanes(
+    turns: Vec<Vec<TurnIndication>>,
+    num_lanes: usize,
+) -> (Vec<Vec<TurnIndication>>, Option<String>) {
+    repair_per_lane("turn:lanes", turns, num_lanes, vec![TurnIndication::None])
+}
+
+// The same repair for any other tag with one entry per lane, like hgv:lanes. unmarked fills in
+// missing entries.
+pub fn repair_per_lane<T: Clone>(
+    key: &str,
+    mut values: Vec<T>,
+    num_lanes: usize,
+    unmarked: T,
+) -> (Vec<T>, Option<String>) {
+    if values.len() == num_lanes {
+        return (values, None);
+    }
+    let warning = format!(
+        "{} has {} entries, but there are {} lanes; {}",
+        key,
+        values.len(),
+        num_lanes,
+        if values.len() > num_lanes {
+            "dropping the extras"
+        } else {
+            "treating the rest as unmarked"
+        }
+    );
+    values.resize(num_lanes, unmarked);
+    (values, Some(warning))
+}
+
+// Which way a vehicle leaves an intersection, ignoring how sharp the turn is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Movement {
+    Left,
+    Through,
+    Right,
+    UTurn,
+}
+
+impl TurnIndication {
+    // None means every movement is allowed.
+    pub fn movement(self) -> Option<Movement> {
+        match self {
+            TurnIndication::None => None,
+            TurnIndication::Left | TurnIndication::SlightLeft | TurnIndication::SharpLeft => {
+                Some(Movement::Left)
+            }
+            TurnIndication::Through
+            | TurnIndication::MergeToLeft
+            | TurnIndication::MergeToRight => Some(Movement::Through),
+            TurnIndication::Right | TurnIndication::SlightRight | TurnIndication::SharpRight => {
+                Some(Movement::Right)
+            }
+            TurnIndication::Reverse => Some(Movement::UTurn),
+        }
+    }
+}
+
+// Matches the approaching lanes (from parse_turn_lanes, ordered left to right) that allow a
+// movement to the lanes they feed on the receiving road, which has num_receiving lanes. Both sides
+// are counted from the side the vehicle turns towards, so two left-turn lanes feed the two leftmost
+// receiving lanes, one each. When there are more feeding lanes than receiving, the extras all
+// share the farthest receiving lane. Unmarked lanes only feed anything when no lane is explicitly
+// marked for the movement. Returns pairs of (approach index, receiving index).
+pub fn connect_turn_lanes(
+    approach: &[Vec<TurnIndication>],
+    movement: Movement,
+    num_receiving: usize,
+) -> Vec<(usize, usize)> {
+    if num_receiving == 0 {
+        return Vec::new();
+    }
+    let marked = |turns: &Vec<TurnIndication>| turns.iter().any(|t| t.movement() == Some(movement));
+    let mut feeders: Vec<usize> = (0..approach.len())
+        .filter(|idx| marked(&approach[*idx]))
+        .collect();
+    if feeders.is_empty() {
+        feeders = (0..approach.len())
+            .filter(|idx| approach[*idx].contains(&TurnIndication::None))
+            .collect();
+    }
+
+    // Turning right, count from the right on both roads
+    let from_right = movement == Movement::Right;
+    if from_right {
+        feeders.reverse();
+    }
+    feeders
+        .into_iter()
+        .enumerate()
+        .map(|(rank, idx)| {
+            let rank = rank.min(num_receiving - 1);
+            if from_right {
+                (idx, num_receiving - 1 - rank)
+            } else {
+                (idx, rank)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        connect_turn_lanes, parse_turn_lanes, repair_per_lane, repair_turn_lanes, Movement,
+        TurnIndication,
+    };
+
+    #[test]
+    fn test_double_left_turn() {
+        let approach = parse_turn_lanes("left|left|through|through;right").unwrap();
+        assert_eq!(
+            vec![(0, 0), (1, 1)],
+            connect_turn_lanes(&approach, Movement::Left, 2)
+        );
+        // Only one lane to receive them
+        assert_eq!(
+            vec![(0, 0), (1, 0)],
+            connect_turn_lanes(&approach, Movement::Left, 1)
+        );
+        assert_eq!(
+            vec![(2, 0), (3, 1)],
+            connect_turn_lanes(&approach, Movement::Through, 3)
+        );
+        assert_eq!(
+            vec![(3, 2)],
+            connect_turn_lanes(&approach, Movement::Right, 3)
+        );
+        assert!(connect_turn_lanes(&approach, Movement::UTurn, 2).is_empty());
+    }
+
+    #[test]
+    fn test_repair_turn_lanes() {
+        let turns = parse_turn_lanes("left|through|through;right").unwrap();
+        let (repaired, warning) = repair_turn_lanes(turns.clone(), 3);
+        assert_eq!(turns, repaired);
+        assert!(warning.is_none());
+
+        // Too many
+        let (repaired, warning) = repair_turn_lanes(turns.clone(), 2);
+        assert_eq!(turns[0..2].to_vec(), repaired);
+        assert!(warning.is_some());
+
+        // Too few
+        let (repaired, warning) = repair_turn_lanes(turns.clone(), 5);
+        assert_eq!(5, repaired.len());
+        assert_eq!(turns, repaired[0..3].to_vec());
+        assert_eq!(vec![TurnIndication::None], repaired[3]);
+        assert_eq!(vec![TurnIndication::None], repaired[4]);
+        assert!(warning.is_some());
+    }
+
+    #[test]
+    fn test_repair_per_lane() {
+        let (repaired, warning) = repair_per_lane("hgv:lanes", vec!["yes", "no"], 3, "");
+        assert_eq!(vec!["yes", "no", ""], repaired);
+        assert_eq!(
+            Some(
+                "hgv:lanes has 2 entries, but there are 3 lanes; treating the rest as unmarked"
+                    .to_string()
+            ),
+            warning
+        );
+    }
+
+    #[test]
+    fn test_unmarked_lanes() {
+        let approach = parse_turn_lanes("|").unwrap();
+        assert_eq!(
+            vec![(1, 1), (0, 0)],
+            connect_turn_lanes(&approach, Movement::Right, 2)
+        );
+        let approach = parse_turn_lanes("|right").unwrap();
+        assert_eq!(
+            vec![(1, 0)],
+            connect_turn_lanes(&approach, Movement::Right, 1)
+        );
+    }
+}
@@ -121,6 +121,9 @@ fn make_vehicle_turns(
     lane_types.remove(&LaneType::Parking);
     lane_types.remove(&LaneType::SharedLeftTurn);
     lane_types.remove(&LaneType::Construction);
+    lane_types.remove(&LaneType::Shoulder);
+    lane_types.remove(&LaneType::Buffer);
+    lane_types.remove(&LaneType::Median);
     lane_types.remove(&LaneType::Sidewalk);
 
     let mut result: Vec<Option<Turn>> = Vec::new();
@@ -5,7 +5,7 @@ use crate::{
     BusStopID, ControlStopSign, ControlTrafficSignal, EditCmd, EditEffects, EditIntersection,
     Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType, MapEdits, ParkingLot,
     ParkingLotID, Path, PathConstraints, PathRequest, Position, Road, RoadID, Turn, TurnGroupID,
-    TurnID, TurnType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
+    TurnID, TurnType,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap, Error, Timer, Warn};
 use geom::{Angle, Bounds, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Speed};
@@ -1027,11 +1027,7 @@ fn make_half_map(
             };
             // TODO probably different behavior for oneways
             // TODO need to factor in yellow center lines (but what's the right thing to even do?
-            let width = if lane.lane_type == LaneType::Sidewalk {
-                SIDEWALK_THICKNESS
-            } else {
-                NORMAL_LANE_THICKNESS
-            };
+            let width = make::initial::lane_specs::default_lane_width(lane.lane_type);
             let lane_center_pts = map
                 .right_shift(unshifted_pts, other_lanes_width + width / 2.0)
                 .with_context(timer, format!("shift for {}", id));
@@ -1043,6 +1039,7 @@ fn make_half_map(
                 src_i,
                 dst_i,
                 lane_type: lane.lane_type,
+                details: lane.details.clone(),
                 parent: road_id,
                 building_paths: Vec::new(),
                 bus_stops: Vec::new(),
@@ -1077,7 +1074,7 @@ fn make_half_map(
         if i.intersection_type == IntersectionType::TrafficSignal {
             let mut ok = false;
             for r in &i.roads {
-                if map.roads[r.0].osm_tags.get(osm::HIGHWAY) != Some(&"construction".to_string()) {
+                if !osm::has_value(&map.roads[r.0].osm_tags, osm::HIGHWAY, "construction") {
                     ok = true;
                     break;
                 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 // These are common OSM keys. Keys used in just one or two places don't really need to be defined
 // here.
 
@@ -10,10 +12,15 @@ pub const PARKING_LEFT: &str = "parking:lane:left";
 pub const PARKING_BOTH: &str = "parking:lane:both";
 pub const SIDEWALK: &str = "sidewalk";
 
+// Values compared against all the time
+pub const YES: &str = "yes";
+pub const NO: &str = "no";
+
 // The rest of these are all inserted by A/B Street to plumb data between different stages of map
 // construction. They could be plumbed another way, but this is the most convenient.
 
-// TODO Comparing to Some(&"true".to_string()) is annoying
+// Flags set to this
+pub const TRUE: &str = "true";
 
 // Just a copy of OSM IDs, so that things displaying/searching tags will also pick these up.
 pub const OSM_WAY_ID: &str = "abst:osm_way_id";
@@ -30,3 +37,26 @@ pub const SYNTHETIC_LANES: &str = "abst:synthetic_lanes";
 // Any roads might have these.
 pub const INFERRED_PARKING: &str = "abst:parking_inferred";
 pub const INFERRED_SIDEWALKS: &str = "abst:sidewalks_inferred";
+
+// Is the key set to exactly this value? Comparing against Some(&value.to_string()) instead
+// allocates, which adds up when importing a large map.
+pub fn has_value(tags: &BTreeMap<String, String>, key: &str, value: &str) -> bool {
+    tags.get(key).map(|x| x.as_str()) == Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_value, HIGHWAY, NO};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_has_value() {
+        let mut tags = BTreeMap::new();
+        tags.insert(HIGHWAY.to_string(), "primary".to_string());
+        tags.insert("oneway".to_string(), NO.to_string());
+        assert!(has_value(&tags, HIGHWAY, "primary"));
+        assert!(!has_value(&tags, HIGHWAY, "prim"));
+        assert!(has_value(&tags, "oneway", NO));
+        assert!(!has_value(&tags, "lanes", NO));
+    }
+}
@@ -0,0 +1,109 @@
+// OSM values with units, like https://wiki.openstreetmap.org/wiki/Map_Features/Units. Anything
+// without a unit uses OSM's default.
+use geom::{Distance, Speed};
+
+// Bare numbers are meters. Also handles km, mi, ft, and the imperial 12'6" notation.
+pub fn parse_distance(value: &str) -> Option<Distance> {
+    let value = value.trim();
+    if let Some(idx) = value.find('\'') {
+        let feet = value[0..idx].trim().parse::<f64>().ok()?;
+        let rest = value[idx + 1..].trim();
+        let inches = if rest.is_empty() {
+            0.0
+        } else if rest.ends_with('"') {
+            rest[0..rest.len() - 1].trim().parse::<f64>().ok()?
+        } else {
+            return None;
+        };
+        return Some(Distance::inches(12.0 * feet + inches));
+    }
+
+    let (number, unit) = split_unit(value);
+    let number = number.parse::<f64>().ok()?;
+    match unit {
+        "" | "m" => Some(Distance::meters(number)),
+        "km" => Some(Distance::meters(1000.0 * number)),
+        "mi" => Some(Distance::miles(number)),
+        "ft" => Some(Distance::inches(12.0 * number)),
+        _ => None,
+    }
+}
+
+// Bare numbers are km/h. Also handles mph and knots.
+pub fn parse_speed(value: &str) -> Option<Speed> {
+    let (number, unit) = split_unit(value.trim());
+    let number = number.parse::<f64>().ok()?;
+    match unit {
+        "" | "km/h" | "kmh" | "kph" => Some(Speed::meters_per_second(number / 3.6)),
+        "mph" => Some(Speed::miles_per_hour(number)),
+        "knots" => Some(Speed::meters_per_second(number * 1852.0 / 3600.0)),
+        _ => None,
+    }
+}
+
+// In metric tonnes. Bare numbers are tonnes too. Also handles kg, short tons, and pounds.
+pub fn parse_weight(value: &str) -> Option<f64> {
+    let (number, unit) = split_unit(value.trim());
+    let number = number.parse::<f64>().ok()?;
+    match unit {
+        "" | "t" => Some(number),
+        "kg" => Some(number / 1000.0),
+        "st" => Some(number * 0.907_185),
+        "lbs" => Some(number * 0.000_453_592),
+        _ => None,
+    }
+}
+
+// "12.5 mph" and "12.5mph" both become ("12.5", "mph")
+fn split_unit(value: &str) -> (&str, &str) {
+    let idx = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or(value.len());
+    (value[0..idx].trim(), value[idx..].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_distance, parse_speed, parse_weight};
+    use geom::{Distance, Speed};
+
+    #[test]
+    fn test_parse_distance() {
+        assert_eq!(Some(Distance::meters(3.5)), parse_distance("3.5"));
+        assert_eq!(Some(Distance::meters(3.5)), parse_distance("3.5 m"));
+        assert_eq!(Some(Distance::meters(3.5)), parse_distance("3.5m"));
+        assert_eq!(Some(Distance::meters(1500.0)), parse_distance("1.5 km"));
+        assert_eq!(Some(Distance::miles(2.0)), parse_distance("2 mi"));
+        assert_eq!(Some(Distance::inches(120.0)), parse_distance("10 ft"));
+        assert_eq!(Some(Distance::inches(144.0)), parse_distance("12'"));
+        assert_eq!(Some(Distance::inches(150.0)), parse_distance("12'6\""));
+        assert_eq!(None, parse_distance("12'6"));
+        assert_eq!(None, parse_distance("wide"));
+        assert_eq!(None, parse_distance("3 furlongs"));
+        assert_eq!(None, parse_distance(""));
+    }
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(Some(Speed::miles_per_hour(25.0)), parse_speed("25 mph"));
+        assert_eq!(Some(Speed::miles_per_hour(25.0)), parse_speed("25mph"));
+        assert_eq!(Some(Speed::meters_per_second(12.5)), parse_speed("45"));
+        assert_eq!(Some(Speed::meters_per_second(12.5)), parse_speed("45 km/h"));
+        assert_eq!(Some(Speed::meters_per_second(12.5)), parse_speed("45 kph"));
+        assert_eq!(
+            Some(Speed::meters_per_second(1852.0 / 360.0)),
+            parse_speed("10 knots")
+        );
+        assert_eq!(None, parse_speed("signals"));
+        assert_eq!(None, parse_speed("25 furlongs/fortnight"));
+    }
+
+    #[test]
+    fn test_parse_weight() {
+        assert_eq!(Some(7.5), parse_weight("7.5"));
+        assert_eq!(Some(7.5), parse_weight("7.5 t"));
+        assert_eq!(Some(3.5), parse_weight("3500 kg"));
+        assert_eq!(Some(9.071_85), parse_weight("10 st"));
+        assert_eq!(None, parse_weight("heavy"));
+    }
+}
@@ -351,9 +351,9 @@ impl PathConstraints {
                 } else if l.is_driving() || l.is_bus() {
                     // Note bikes can use bus lanes -- this is generally true in Seattle.
                     let road = map.get_r(l.parent);
-                    road.osm_tags.get("bicycle") != Some(&"no".to_string())
-                        && road.osm_tags.get(osm::HIGHWAY) != Some(&"motorway".to_string())
-                        && road.osm_tags.get(osm::HIGHWAY) != Some(&"motorway_link".to_string())
+                    !osm::has_value(&road.osm_tags, "bicycle", osm::NO)
+                        && !osm::has_value(&road.osm_tags, osm::HIGHWAY, "motorway")
+                        && !osm::has_value(&road.osm_tags, osm::HIGHWAY, "motorway_link")
                 } else {
                     false
                 }
@@ -166,7 +166,7 @@ impl RawMap {
         };
         let mut roads = BTreeMap::new();
         for r in &i.roads {
-            roads.insert(*r, initial::Road::new(*r, &self.roads[r]));
+            roads.insert(*r, initial::Road::new(*r, &self.roads[r], timer));
         }
 
         let (i_pts, debug) =
@@ -302,7 +302,7 @@ impl RawRoad {
     }
 
     pub fn synthetic(&self) -> bool {
-        self.osm_tags.get(osm::SYNTHETIC) == Some(&"true".to_string())
+        osm::has_value(&self.osm_tags, osm::SYNTHETIC, osm::TRUE)
     }
 }
 
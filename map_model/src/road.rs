@@ -1,3 +1,5 @@
+use crate::make::initial::lane_specs;
+use crate::parse_units::parse_speed;
 use crate::raw::{OriginalRoad, RestrictionType};
 use crate::{osm, BusStopID, IntersectionID, LaneID, LaneType, Map, PathConstraints};
 use abstutil::{Error, Warn};
@@ -208,21 +210,12 @@ impl Road {
     }
 
     pub(crate) fn speed_limit_from_osm(&self) -> Speed {
-        if let Some(limit) = self.osm_tags.get(osm::MAXSPEED) {
-            // TODO handle other units
-            if limit.ends_with(" mph") {
-                if let Ok(mph) = limit[0..limit.len() - 4].parse::<f64>() {
-                    return Speed::miles_per_hour(mph);
-                }
-            }
-        }
+        speed_limit_from_osm_tags(&self.osm_tags)
+    }
 
-        if self.osm_tags.get(osm::HIGHWAY) == Some(&"primary".to_string())
-            || self.osm_tags.get(osm::HIGHWAY) == Some(&"secondary".to_string())
-        {
-            return Speed::miles_per_hour(40.0);
-        }
-        Speed::miles_per_hour(20.0)
+    // Separate from speed_limit, since it's only a recommendation for curves and ramps
+    pub fn advisory_speed_limit(&self) -> Option<Speed> {
+        lane_specs::advisory_speed_limit(&self.osm_tags)
     }
 
     pub fn incoming_lanes(&self, i: IntersectionID) -> &Vec<(LaneID, LaneType)> {
@@ -450,3 +443,67 @@ impl Road {
         grade
     }
 }
+
+// A maxspeed without units is in km/h, per https://wiki.openstreetmap.org/wiki/Key:maxspeed.
+// Anything unparseable (like "signals") falls back to a default for the road class.
+fn speed_limit_from_osm_tags(osm_tags: &BTreeMap<String, String>) -> Speed {
+    if let Some(limit) = osm_tags.get(osm::MAXSPEED).and_then(|x| parse_speed(x)) {
+        return limit;
+    }
+
+    if osm::has_value(osm_tags, osm::HIGHWAY, "primary")
+        || osm::has_value(osm_tags, osm::HIGHWAY, "secondary")
+    {
+        return Speed::miles_per_hour(40.0);
+    }
+    Speed::miles_per_hour(20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::speed_limit_from_osm_tags;
+    use geom::Speed;
+    use std::collections::BTreeMap;
+
+    fn tags(kv: Vec<&str>) -> BTreeMap<String, String> {
+        kv.into_iter()
+            .map(|x| {
+                let parts: Vec<&str> = x.split('=').collect();
+                (parts[0].to_string(), parts[1].to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_speed_limit_from_osm() {
+        for (input, expected) in &[
+            (
+                vec!["highway=primary", "maxspeed=30 mph"],
+                Speed::miles_per_hour(30.0),
+            ),
+            // Bare numbers are km/h, not the class default
+            (
+                vec!["highway=primary", "maxspeed=50"],
+                Speed::meters_per_second(50.0 / 3.6),
+            ),
+            (
+                vec!["highway=residential", "maxspeed=30"],
+                Speed::meters_per_second(30.0 / 3.6),
+            ),
+            // Unparseable or missing values use the class default
+            (
+                vec!["highway=primary", "maxspeed=signals"],
+                Speed::miles_per_hour(40.0),
+            ),
+            (vec!["highway=secondary"], Speed::miles_per_hour(40.0)),
+            (vec!["highway=residential"], Speed::miles_per_hour(20.0)),
+        ] {
+            assert_eq!(
+                *expected,
+                speed_limit_from_osm_tags(&tags(input.clone())),
+                "{:?}",
+                input
+            );
+        }
+    }
+}